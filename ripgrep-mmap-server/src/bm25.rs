@@ -0,0 +1,106 @@
+//! BM25 relevance scoring over the cached corpus, used by `/search`'s ranked mode to
+//! order files by how well they match the query rather than by filesystem/candidate
+//! order. Term statistics are gathered once in `MmapCache::new` (same lifecycle as
+//! `TrigramIndex`) so scoring a request is just a lookup, not a re-scan of every file.
+
+use memmap2::Mmap;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// `k1` controls how quickly additional occurrences of a term stop adding to the score
+/// (term-frequency saturation); `b` controls how much a file's length is penalized
+/// relative to the corpus average. Both are the standard defaults used by most BM25
+/// implementations (e.g. Lucene, Elasticsearch).
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// Per-file term frequencies and corpus-wide document frequencies needed to score a
+/// query with BM25. File ids are positions into the `Vec<(PathBuf, Mmap)>` passed to
+/// `build`, matching `TrigramIndex`'s convention.
+pub struct Bm25Index {
+    /// Term -> occurrence count, one map per file id.
+    term_freqs: Vec<HashMap<String, u32>>,
+    /// Token count per file id - `|D|` in the BM25 formula.
+    doc_lengths: Vec<u32>,
+    /// Number of files each term occurs in at least once - `n(t)`.
+    doc_freqs: HashMap<String, u32>,
+    /// Average token count across the corpus - `avgdl`. Zero (and every score zero)
+    /// when the corpus is empty or every file tokenized to nothing.
+    avgdl: f64,
+}
+
+impl Bm25Index {
+    /// Build the index over an ordered list of files, indexed by position.
+    pub fn build(files: &[(PathBuf, Mmap)]) -> Self {
+        let mut term_freqs = Vec::with_capacity(files.len());
+        let mut doc_lengths = Vec::with_capacity(files.len());
+        let mut doc_freqs: HashMap<String, u32> = HashMap::new();
+        let mut total_len = 0u64;
+
+        for (_, mmap) in files {
+            let text = String::from_utf8_lossy(&mmap[..]);
+            let mut freqs: HashMap<String, u32> = HashMap::new();
+            let mut len = 0u32;
+            for token in tokenize(&text) {
+                *freqs.entry(token.to_ascii_lowercase()).or_insert(0) += 1;
+                len += 1;
+            }
+            for term in freqs.keys() {
+                *doc_freqs.entry(term.clone()).or_insert(0) += 1;
+            }
+            total_len += len as u64;
+            doc_lengths.push(len);
+            term_freqs.push(freqs);
+        }
+
+        let avgdl = if files.is_empty() {
+            0.0
+        } else {
+            total_len as f64 / files.len() as f64
+        };
+
+        Self {
+            term_freqs,
+            doc_lengths,
+            doc_freqs,
+            avgdl,
+        }
+    }
+
+    /// Score file `file_id` against a query already split into terms (see
+    /// `tokenize_query`) - callers scoring the same query against many files should
+    /// tokenize it once up front rather than per file.
+    pub fn score(&self, file_id: usize, query_terms: &[String]) -> f64 {
+        if self.avgdl <= 0.0 {
+            return 0.0;
+        }
+
+        let n = self.term_freqs.len() as f64;
+        let doc_len = self.doc_lengths[file_id] as f64;
+        let freqs = &self.term_freqs[file_id];
+
+        query_terms
+            .iter()
+            .map(|term| {
+                let f = *freqs.get(term).unwrap_or(&0) as f64;
+                if f == 0.0 {
+                    return 0.0;
+                }
+                let n_t = *self.doc_freqs.get(term).unwrap_or(&0) as f64;
+                let idf = ((n - n_t + 0.5) / (n_t + 0.5) + 1.0).ln();
+                idf * (f * (K1 + 1.0)) / (f + K1 * (1.0 - B + B * doc_len / self.avgdl))
+            })
+            .sum()
+    }
+}
+
+/// Split `pattern` into the lowercased query terms `score` looks up, using the same
+/// tokenization as indexing so both sides agree on what a "term" is.
+pub fn tokenize_query(pattern: &str) -> Vec<String> {
+    tokenize(pattern).map(|t| t.to_ascii_lowercase()).collect()
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|t| !t.is_empty())
+}
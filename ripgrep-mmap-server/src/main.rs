@@ -1,23 +1,51 @@
 use anyhow::{Context, Result};
 use axum::{
+    body::Body,
     extract::{Query, State},
-    http::StatusCode,
+    http::{HeaderValue, StatusCode},
     response::{IntoResponse, Json},
     routing::{get, post},
     Router,
 };
+use crossbeam_channel::bounded;
 use grep_regex::RegexMatcherBuilder;
-use grep_searcher::sinks::UTF8;
-use grep_searcher::Searcher;
+use grep_searcher::{
+    BinaryDetection, Searcher, SearcherBuilder, Sink, SinkContext, SinkContextKind, SinkFinish, SinkMatch,
+};
 use memmap2::Mmap;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
-use tracing::{info, warn};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+use tracing::{debug, info, warn};
+use twox_hash::xxh3::hash128;
+
+mod bm25;
+mod trigram;
+
+use bm25::Bm25Index;
+use trigram::TrigramIndex;
+
+/// Matches buffered into the channel a streaming search sends over, before backpressure
+/// kicks in - mirrors the bound used by the other streaming search in this workspace.
+const STREAM_CHANNEL_CAPACITY: usize = 256;
+
+/// Bytes sampled from the start of a file to decide whether it's binary, mirroring
+/// ripgrep's own heuristic: a NUL byte anywhere in the sample means binary.
+const BINARY_SAMPLE_SIZE: usize = 8192;
+
+/// Whether `data` looks like a binary file, judged from a leading sample rather than the
+/// whole file so indexing a large binary doesn't require scanning all of it.
+fn looks_binary(data: &[u8]) -> bool {
+    let sample = &data[..data.len().min(BINARY_SAMPLE_SIZE)];
+    sample.contains(&0)
+}
 
 /// Represents a single search match
 #[derive(Debug, Clone, Serialize)]
@@ -26,6 +54,12 @@ struct SearchMatch {
     line_number: u64,
     line: String,
     byte_offset: u64,
+    /// Lines immediately preceding the match, oldest first (`grep -B`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    before: Vec<String>,
+    /// Lines immediately following the match (`grep -A`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    after: Vec<String>,
 }
 
 /// Search request parameters
@@ -36,6 +70,37 @@ struct SearchRequest {
     case_sensitive: bool,
     #[serde(default = "default_max_results")]
     max_results: usize,
+    /// Lines of context to show before each match (`grep -B`).
+    #[serde(default)]
+    before_context: usize,
+    /// Lines of context to show after each match (`grep -A`).
+    #[serde(default)]
+    after_context: usize,
+    /// Shorthand for `before_context`/`after_context` together (`grep -C`); an explicit
+    /// `before_context`/`after_context` takes precedence when larger.
+    #[serde(default)]
+    context: usize,
+    /// Only search paths matching these globs (ripgrep's `--glob`), e.g. `"*.rs"` or
+    /// `"!vendor/**"` to exclude.
+    #[serde(default)]
+    globs: Vec<String>,
+    /// Only search files of these types (ripgrep's `--type`), e.g. `"rust"`, `"js"`.
+    #[serde(default)]
+    types: Vec<String>,
+    /// Order results by BM25 relevance (pattern tokenized into query terms against each
+    /// candidate file's indexed term frequencies) instead of filesystem/candidate order.
+    #[serde(default)]
+    rank: bool,
+}
+
+impl SearchRequest {
+    fn before_context(&self) -> usize {
+        self.before_context.max(self.context)
+    }
+
+    fn after_context(&self) -> usize {
+        self.after_context.max(self.context)
+    }
 }
 
 fn default_max_results() -> usize {
@@ -51,17 +116,120 @@ struct SearchResponse {
     duration_ms: u128,
 }
 
+/// Query parameters for `GET /duplicates`
+#[derive(Debug, Deserialize)]
+struct DuplicatesRequest {
+    /// Only report groups with at least this many files (default 2 - i.e. any
+    /// duplicate at all).
+    #[serde(default = "default_min_group_size")]
+    min_group_size: usize,
+    /// Skip files smaller than this many bytes, to avoid flooding the result with tiny
+    /// incidental duplicates (empty files, single-byte files, etc).
+    #[serde(default)]
+    min_size: u64,
+}
+
+fn default_min_group_size() -> usize {
+    2
+}
+
+/// One set of files sharing identical content
+#[derive(Debug, Serialize)]
+struct DuplicateGroup {
+    size: u64,
+    paths: Vec<String>,
+}
+
+/// Response for `GET /duplicates`
+#[derive(Debug, Serialize)]
+struct DuplicatesResponse {
+    groups: Vec<DuplicateGroup>,
+    files_scanned: usize,
+    duration_ms: u128,
+}
+
+/// Glob (`--glob`) and file-type (`--type`) scoping for a search. Built fresh per
+/// request from `SearchRequest::globs`/`types`, same as ripgrep resolves its CLI flags
+/// into an `Override`/`Types` pair per invocation.
+struct PathFilter {
+    overrides: Option<ignore::overrides::Override>,
+    types: Option<ignore::types::Types>,
+}
+
+impl PathFilter {
+    fn build(root: &Path, request: &SearchRequest) -> Result<Self> {
+        let overrides = if request.globs.is_empty() {
+            None
+        } else {
+            let mut builder = ignore::overrides::OverrideBuilder::new(root);
+            for glob in &request.globs {
+                builder
+                    .add(glob)
+                    .with_context(|| format!("Invalid glob pattern: {}", glob))?;
+            }
+            Some(builder.build().context("Failed to build glob overrides")?)
+        };
+
+        let types = if request.types.is_empty() {
+            None
+        } else {
+            let mut builder = ignore::types::TypesBuilder::new();
+            builder.add_defaults();
+            for type_name in &request.types {
+                builder.select(type_name);
+            }
+            Some(builder.build().context("Failed to build type filters")?)
+        };
+
+        Ok(Self { overrides, types })
+    }
+
+    /// Whether `path` passes both the glob overrides and the type filter (a filter
+    /// that wasn't requested always passes).
+    fn allows(&self, path: &Path) -> bool {
+        let glob_ok = self.overrides.as_ref().map_or(true, |overrides| {
+            match overrides.matched(path, false) {
+                // A `!pattern` glob explicitly excludes this path.
+                ignore::Match::Ignore(_) => false,
+                // Matched one of the (non-negated) globs.
+                ignore::Match::Whitelist(_) => true,
+                // No glob matched at all: allowed, unless there are whitelist globs
+                // in play, in which case an explicit match is required (an
+                // exclude-only override set like `["!vendor/**"]` must not reject
+                // everything it doesn't mention).
+                ignore::Match::None => overrides.num_whitelists() == 0,
+            }
+        });
+        let type_ok = self.types.as_ref().map_or(true, |types| {
+            matches!(types.matched(path, false), ignore::Match::Whitelist(_))
+        });
+        glob_ok && type_ok
+    }
+}
+
 /// Memory-mapped file cache
 struct MmapCache {
-    files: HashMap<PathBuf, Mmap>,
+    /// Indexed by position - a file's index into this `Vec` is its "file id" as used by
+    /// `trigram_index`'s posting lists.
+    files: Vec<(PathBuf, Mmap)>,
     root: PathBuf,
+    /// Trigram index over `files`, used to prune candidate files before regex-scanning.
+    trigram_index: TrigramIndex,
+    /// BM25 term statistics over `files`, used to rank results when `SearchRequest::rank`
+    /// is set.
+    bm25_index: Bm25Index,
+    /// Whether binary files were indexed (and should be regex-scanned without ripgrep's
+    /// usual NUL-byte bail-out) rather than skipped, mirrored from the flag `new` was
+    /// built with so `/reload` can reuse it.
+    include_binary: bool,
 }
 
 impl MmapCache {
-    /// Create a new cache by memory-mapping all files in the given directory
-    fn new(root: &Path) -> Result<Self> {
+    /// Create a new cache by memory-mapping all files in the given directory. Files that
+    /// look binary (see `looks_binary`) are skipped unless `include_binary` is set.
+    fn new(root: &Path, include_binary: bool) -> Result<Self> {
         info!("Building memory-mapped cache for: {}", root.display());
-        let mut files = HashMap::new();
+        let mut files = Vec::new();
         let mut file_count = 0;
         let mut total_bytes = 0u64;
 
@@ -82,17 +250,6 @@ impl MmapCache {
 
             let path = entry.path();
 
-            // Skip binary files heuristic - check file extension
-            if let Some(ext) = path.extension() {
-                let ext_str = ext.to_string_lossy();
-                if matches!(
-                    ext_str.as_ref(),
-                    "png" | "jpg" | "jpeg" | "gif" | "pdf" | "zip" | "tar" | "gz" | "so" | "dylib" | "dll" | "exe" | "bin" | "o" | "a"
-                ) {
-                    continue;
-                }
-            }
-
             match File::open(path) {
                 Ok(file) => {
                     let metadata = file.metadata()?;
@@ -111,9 +268,12 @@ impl MmapCache {
 
                     match unsafe { Mmap::map(&file) } {
                         Ok(mmap) => {
+                            if !include_binary && looks_binary(&mmap[..]) {
+                                continue;
+                            }
                             file_count += 1;
                             total_bytes += file_size;
-                            files.insert(path.to_owned(), mmap);
+                            files.push((path.to_owned(), mmap));
                         }
                         Err(e) => {
                             warn!("Failed to mmap {}: {}", path.display(), e);
@@ -132,12 +292,45 @@ impl MmapCache {
             total_bytes as f64 / 1024.0 / 1024.0
         );
 
+        let trigram_index = TrigramIndex::build(&files);
+        let bm25_index = Bm25Index::build(&files);
+
         Ok(Self {
             files,
             root: root.to_owned(),
+            trigram_index,
+            bm25_index,
+            include_binary,
         })
     }
 
+    /// Files that may contain a match for `request`, narrowed via the trigram index
+    /// when it can extract a mandatory literal and via `globs`/`types` scoping;
+    /// otherwise every file in the cache. Each candidate keeps its file id (its index
+    /// into `self.files`) alongside the entry so callers can look it up in
+    /// `bm25_index` without re-deriving it.
+    fn candidate_files(&self, request: &SearchRequest) -> Result<Vec<(usize, &(PathBuf, Mmap))>> {
+        let mut targets = match self.trigram_index.candidates(&request.pattern) {
+            Some(ids) => {
+                debug!(
+                    "Trigram index narrowed '{}' to {}/{} candidate files",
+                    request.pattern,
+                    ids.len(),
+                    self.files.len()
+                );
+                ids.iter().map(|&id| (id as usize, &self.files[id as usize])).collect()
+            }
+            None => self.files.iter().enumerate().collect::<Vec<_>>(),
+        };
+
+        if !request.globs.is_empty() || !request.types.is_empty() {
+            let filter = PathFilter::build(&self.root, request)?;
+            targets.retain(|(_, (path, _))| filter.allows(path));
+        }
+
+        Ok(targets)
+    }
+
     /// Search all memory-mapped files for the given pattern
     fn search(&self, request: &SearchRequest) -> Result<SearchResponse> {
         let start = std::time::Instant::now();
@@ -148,13 +341,26 @@ impl MmapCache {
             .build(&request.pattern)
             .context("Invalid regex pattern")?;
 
-        // Search all files in parallel using rayon
-        let all_matches: Vec<Vec<SearchMatch>> = self
-            .files
-            .par_iter()
-            .map(|(path, mmap)| {
-                let mut matches = Vec::new();
-                let mut searcher = Searcher::new();
+        let targets = self.candidate_files(request)?;
+        let files_searched = targets.len();
+        let before_context = request.before_context();
+        let after_context = request.after_context();
+        let binary_detection = if self.include_binary {
+            BinaryDetection::none()
+        } else {
+            BinaryDetection::quit(0)
+        };
+
+        // Search candidate files in parallel using rayon
+        let mut all_matches: Vec<(usize, Vec<SearchMatch>)> = targets
+            .into_par_iter()
+            .map(|(file_id, (path, mmap))| {
+                let mut searcher = SearcherBuilder::new()
+                    .before_context(before_context)
+                    .after_context(after_context)
+                    .line_number(true)
+                    .binary_detection(binary_detection.clone())
+                    .build();
 
                 // Convert path to string relative to root
                 let rel_path = path
@@ -163,37 +369,34 @@ impl MmapCache {
                     .to_string_lossy()
                     .to_string();
 
-                // Search this file's memory-mapped contents
-                let result = searcher.search_slice(
-                    &matcher,
-                    &mmap[..],
-                    UTF8(|line_num, line| {
-                        // Stop if we've hit the max results
-                        if matches.len() >= request.max_results {
-                            return Ok(false);
-                        }
-
-                        matches.push(SearchMatch {
-                            path: rel_path.clone(),
-                            line_number: line_num,
-                            line: line.trim_end().to_string(),
-                            byte_offset: 0, // We could calculate this if needed
-                        });
+                let mut matches = Vec::new();
+                let sink = ContextSink::new(&rel_path, request.max_results, before_context, &mut matches);
 
-                        Ok(true) // Continue searching
-                    }),
-                );
+                // Search this file's memory-mapped contents
+                let result = searcher.search_slice(&matcher, &mmap[..], sink);
 
                 if let Err(e) = result {
                     warn!("Search error in {}: {}", path.display(), e);
                 }
 
-                matches
+                (file_id, matches)
             })
             .collect();
 
+        // Ranked mode: order files by BM25 relevance to the pattern rather than the
+        // order they came back from the parallel scan above.
+        if request.rank {
+            let query_terms = bm25::tokenize_query(&request.pattern);
+            let mut scored: Vec<(f64, usize, Vec<SearchMatch>)> = all_matches
+                .into_iter()
+                .map(|(file_id, matches)| (self.bm25_index.score(file_id, &query_terms), file_id, matches))
+                .collect();
+            scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+            all_matches = scored.into_iter().map(|(_, file_id, matches)| (file_id, matches)).collect();
+        }
+
         // Flatten results and apply global max_results limit
-        let mut matches: Vec<SearchMatch> = all_matches.into_iter().flatten().collect();
+        let mut matches: Vec<SearchMatch> = all_matches.into_iter().flat_map(|(_, m)| m).collect();
         let total_matches = matches.len();
         matches.truncate(request.max_results);
 
@@ -202,15 +405,348 @@ impl MmapCache {
         Ok(SearchResponse {
             matches,
             total_matches,
-            files_searched: self.files.len(),
+            files_searched,
             duration_ms: duration.as_millis(),
         })
     }
+
+    /// Search all memory-mapped files, streaming matches over a channel as each file's
+    /// scan finishes instead of collecting everything into one `SearchResponse`.
+    ///
+    /// Takes `cache` as an already-cloned `Arc` snapshot (not the shared `RwLock`) so the
+    /// rayon pass - which can run for as long as the client takes to drain the channel -
+    /// never holds up a concurrent `/reload` or other readers.
+    fn search_streaming(
+        cache: Arc<MmapCache>,
+        request: SearchRequest,
+        cancel: Arc<AtomicBool>,
+    ) -> crossbeam_channel::Receiver<SearchMatch> {
+        let (tx, rx) = bounded(STREAM_CHANNEL_CAPACITY);
+
+        std::thread::spawn(move || {
+            let matcher = match RegexMatcherBuilder::new()
+                .case_insensitive(!request.case_sensitive)
+                .build(&request.pattern)
+            {
+                Ok(matcher) => matcher,
+                Err(e) => {
+                    warn!("Invalid regex pattern for streaming search: {}", e);
+                    return;
+                }
+            };
+
+            let sent = Arc::new(AtomicUsize::new(0));
+            let targets = match cache.candidate_files(&request) {
+                Ok(targets) => targets,
+                Err(e) => {
+                    warn!("Invalid glob/type filter for streaming search: {}", e);
+                    return;
+                }
+            };
+            let before_context = request.before_context();
+            let after_context = request.after_context();
+            let binary_detection = if cache.include_binary {
+                BinaryDetection::none()
+            } else {
+                BinaryDetection::quit(0)
+            };
+
+            targets.into_par_iter().for_each_with(tx, |tx, (_file_id, (path, mmap))| {
+                if cancel.load(Ordering::Relaxed) || sent.load(Ordering::Relaxed) >= request.max_results {
+                    return;
+                }
+
+                let mut searcher = SearcherBuilder::new()
+                    .before_context(before_context)
+                    .after_context(after_context)
+                    .line_number(true)
+                    .binary_detection(binary_detection.clone())
+                    .build();
+                let rel_path = path
+                    .strip_prefix(&cache.root)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .to_string();
+
+                let sink = StreamingContextSink {
+                    rel_path: &rel_path,
+                    tx: &*tx,
+                    cancel: &cancel,
+                    sent: &sent,
+                    max_results: request.max_results,
+                    before_context,
+                    before_buf: VecDeque::with_capacity(before_context),
+                    pending: None,
+                };
+
+                let result = searcher.search_slice(&matcher, &mmap[..], sink);
+
+                if let Err(e) = result {
+                    warn!("Search error in {}: {}", path.display(), e);
+                }
+            });
+        });
+
+        rx
+    }
+
+    /// Group cached files by identical content. Two-staged to avoid hashing the whole
+    /// corpus: first bucket by exact byte length (free - it's just `mmap.len()`), then
+    /// only within a bucket with more than one file is a content hash worth computing,
+    /// and those hashes are computed in parallel with rayon.
+    fn find_duplicates(&self, request: &DuplicatesRequest) -> DuplicatesResponse {
+        let start = std::time::Instant::now();
+
+        let mut by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (file_id, (_, mmap)) in self.files.iter().enumerate() {
+            let size = mmap.len() as u64;
+            if size < request.min_size {
+                continue;
+            }
+            by_size.entry(size).or_default().push(file_id);
+        }
+
+        let groups: Vec<DuplicateGroup> = by_size
+            .into_par_iter()
+            .filter(|(_, file_ids)| file_ids.len() > 1)
+            .flat_map_iter(|(size, file_ids)| {
+                let mut by_hash: HashMap<u128, Vec<usize>> = HashMap::new();
+                for file_id in file_ids {
+                    let hash = hash128(&self.files[file_id].1[..]);
+                    by_hash.entry(hash).or_default().push(file_id);
+                }
+
+                by_hash
+                    .into_iter()
+                    .filter(move |(_, ids)| ids.len() >= request.min_group_size)
+                    .map(move |(_, ids)| DuplicateGroup {
+                        size,
+                        paths: ids
+                            .into_iter()
+                            .map(|id| {
+                                self.files[id]
+                                    .0
+                                    .strip_prefix(&self.root)
+                                    .unwrap_or(&self.files[id].0)
+                                    .to_string_lossy()
+                                    .to_string()
+                            })
+                            .collect(),
+                    })
+            })
+            .collect();
+
+        DuplicatesResponse {
+            groups,
+            files_scanned: self.files.len(),
+            duration_ms: start.elapsed().as_millis(),
+        }
+    }
+}
+
+/// Collects match lines into `SearchMatch`es, attaching up to `before_context`/
+/// `after_context` surrounding lines. Implements `grep_searcher::Sink` directly rather
+/// than using the `UTF8` convenience sink, since `UTF8` only ever calls back for
+/// matched lines and silently drops `Searcher`'s context lines.
+///
+/// `matches` is an external accumulator rather than owned here so the populated
+/// results are still reachable after `Searcher::search_slice` consumes the sink.
+struct ContextSink<'a> {
+    rel_path: &'a str,
+    max_results: usize,
+    before_context: usize,
+    matches: &'a mut Vec<SearchMatch>,
+    before_buf: VecDeque<String>,
+    /// Index into `matches` of the match still accumulating `after` lines.
+    pending_after: Option<usize>,
+}
+
+impl<'a> ContextSink<'a> {
+    fn new(
+        rel_path: &'a str,
+        max_results: usize,
+        before_context: usize,
+        matches: &'a mut Vec<SearchMatch>,
+    ) -> Self {
+        Self {
+            rel_path,
+            max_results,
+            before_context,
+            matches,
+            before_buf: VecDeque::with_capacity(before_context),
+            pending_after: None,
+        }
+    }
+}
+
+impl<'a> Sink for ContextSink<'a> {
+    type Error = std::io::Error;
+
+    fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        if self.matches.len() >= self.max_results {
+            return Ok(false);
+        }
+
+        self.matches.push(SearchMatch {
+            path: self.rel_path.to_string(),
+            line_number: mat.line_number().unwrap_or(0),
+            line: String::from_utf8_lossy(mat.bytes()).trim_end().to_string(),
+            byte_offset: 0,
+            before: self.before_buf.drain(..).collect(),
+            after: Vec::new(),
+        });
+        self.pending_after = Some(self.matches.len() - 1);
+        Ok(true)
+    }
+
+    fn context(&mut self, _searcher: &Searcher, ctx: &SinkContext<'_>) -> Result<bool, Self::Error> {
+        let line = String::from_utf8_lossy(ctx.bytes()).trim_end().to_string();
+        match ctx.kind() {
+            SinkContextKind::Before => {
+                if self.before_context > 0 {
+                    if self.before_buf.len() >= self.before_context {
+                        self.before_buf.pop_front();
+                    }
+                    self.before_buf.push_back(line);
+                }
+            }
+            SinkContextKind::After => {
+                if let Some(idx) = self.pending_after {
+                    self.matches[idx].after.push(line);
+                }
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn context_break(&mut self, _searcher: &Searcher) -> Result<bool, Self::Error> {
+        // A gap in the reported lines means no more `after` context is coming for
+        // whichever match was still accumulating it.
+        self.pending_after = None;
+        Ok(true)
+    }
+}
+
+/// Streaming counterpart of `ContextSink`: instead of accumulating into a `Vec`, each
+/// match is held until its `after_context` lines are in (or a context break/end of
+/// file arrives) and then sent down the channel, honoring the same cancellation and
+/// `max_results` bookkeeping as the rest of `search_streaming`.
+struct StreamingContextSink<'a> {
+    rel_path: &'a str,
+    tx: &'a crossbeam_channel::Sender<SearchMatch>,
+    cancel: &'a AtomicBool,
+    sent: &'a AtomicUsize,
+    max_results: usize,
+    before_context: usize,
+    before_buf: VecDeque<String>,
+    pending: Option<SearchMatch>,
+}
+
+impl<'a> StreamingContextSink<'a> {
+    fn flush_pending(&mut self) {
+        if let Some(m) = self.pending.take() {
+            // `Err` means the receiver hung up (client disconnected); there's nothing
+            // further to do about it here, the outer rayon pass checks `cancel`.
+            let _ = self.tx.send(m);
+        }
+    }
+}
+
+impl<'a> Sink for StreamingContextSink<'a> {
+    type Error = std::io::Error;
+
+    fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Ok(false);
+        }
+        self.flush_pending();
+        if self.sent.fetch_add(1, Ordering::Relaxed) >= self.max_results {
+            // Over the limit - someone else's increment tipped it past max_results
+            // first; stop this file's scan too.
+            self.cancel.store(true, Ordering::Relaxed);
+            return Ok(false);
+        }
+
+        self.pending = Some(SearchMatch {
+            path: self.rel_path.to_string(),
+            line_number: mat.line_number().unwrap_or(0),
+            line: String::from_utf8_lossy(mat.bytes()).trim_end().to_string(),
+            byte_offset: 0,
+            before: self.before_buf.drain(..).collect(),
+            after: Vec::new(),
+        });
+        Ok(true)
+    }
+
+    fn context(&mut self, _searcher: &Searcher, ctx: &SinkContext<'_>) -> Result<bool, Self::Error> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Ok(false);
+        }
+        let line = String::from_utf8_lossy(ctx.bytes()).trim_end().to_string();
+        match ctx.kind() {
+            SinkContextKind::Before => {
+                if self.before_context > 0 {
+                    if self.before_buf.len() >= self.before_context {
+                        self.before_buf.pop_front();
+                    }
+                    self.before_buf.push_back(line);
+                }
+            }
+            SinkContextKind::After => {
+                if let Some(m) = self.pending.as_mut() {
+                    m.after.push(line);
+                }
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn context_break(&mut self, _searcher: &Searcher) -> Result<bool, Self::Error> {
+        self.flush_pending();
+        Ok(true)
+    }
+
+    fn finish(&mut self, _searcher: &Searcher, _finish: &SinkFinish) -> Result<(), Self::Error> {
+        self.flush_pending();
+        Ok(())
+    }
+}
+
+/// Drops a search's cancel-flag registration once its stream finishes or is abandoned,
+/// so `/search/cancel` can't target a search that no longer exists and the registry
+/// doesn't grow unbounded across many short-lived streaming requests.
+struct SearchGuard {
+    state: Arc<AppState>,
+    search_id: String,
+}
+
+impl Drop for SearchGuard {
+    fn drop(&mut self) {
+        self.state.active_searches.lock().unwrap().remove(&self.search_id);
+    }
 }
 
 /// Application state shared across requests
 struct AppState {
-    cache: Arc<RwLock<MmapCache>>,
+    /// The `Arc<MmapCache>` itself is swapped out wholesale on reload; readers clone it
+    /// and drop the lock immediately instead of holding the lock for the duration of a
+    /// search, so one slow streaming client can't stall `/reload` or other requests.
+    cache: Arc<RwLock<Arc<MmapCache>>>,
+    /// Cancel flags for in-flight `/search/stream` requests, keyed by a server-assigned
+    /// search_id, so `/search/cancel` can flip the matching flag.
+    active_searches: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    next_search_id: AtomicU64,
+    /// Whether to index and search binary files, passed through to `MmapCache::new` on
+    /// both startup and `/reload` so the two stay consistent.
+    include_binary: bool,
+}
+
+/// Body for `POST /search/cancel`
+#[derive(Debug, Deserialize)]
+struct CancelRequest {
+    search_id: String,
 }
 
 /// Health check endpoint
@@ -223,7 +759,7 @@ async fn search_handler(
     State(state): State<Arc<AppState>>,
     Query(request): Query<SearchRequest>,
 ) -> impl IntoResponse {
-    let cache = state.cache.read().await;
+    let cache = Arc::clone(&*state.cache.read().await);
 
     match cache.search(&request) {
         Ok(response) => {
@@ -251,7 +787,7 @@ async fn search_post_handler(
     State(state): State<Arc<AppState>>,
     Json(request): Json<SearchRequest>,
 ) -> impl IntoResponse {
-    let cache = state.cache.read().await;
+    let cache = Arc::clone(&*state.cache.read().await);
 
     match cache.search(&request) {
         Ok(response) => {
@@ -274,20 +810,115 @@ async fn search_post_handler(
     }
 }
 
+/// Streaming search endpoint: emits matches as newline-delimited JSON, one `SearchMatch`
+/// per line, as each file's scan completes rather than after the full search finishes.
+/// The server-assigned search_id for cancelling this search is returned in the
+/// `X-Search-Id` response header.
+async fn search_stream_handler(
+    State(state): State<Arc<AppState>>,
+    Query(request): Query<SearchRequest>,
+) -> impl IntoResponse {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let search_id = state.next_search_id.fetch_add(1, Ordering::Relaxed).to_string();
+    state
+        .active_searches
+        .lock()
+        .unwrap()
+        .insert(search_id.clone(), Arc::clone(&cancel));
+
+    info!(
+        "Streaming search '{}' started (search_id {})",
+        request.pattern, search_id
+    );
+
+    let cache = Arc::clone(&*state.cache.read().await);
+    let rx = MmapCache::search_streaming(cache, request, cancel);
+    let (tokio_tx, tokio_rx) = tokio::sync::mpsc::channel(STREAM_CHANNEL_CAPACITY);
+
+    let guard = SearchGuard {
+        state: Arc::clone(&state),
+        search_id: search_id.clone(),
+    };
+    tokio::task::spawn_blocking(move || {
+        let _guard = guard; // dropped (and cleans up the registry) once this loop ends
+        for m in rx.iter() {
+            if tokio_tx.blocking_send(m).is_err() {
+                break; // client disconnected, stop draining the search
+            }
+        }
+    });
+
+    let body_stream = ReceiverStream::new(tokio_rx).map(|m| {
+        let mut line = serde_json::to_string(&m).unwrap_or_default();
+        line.push('\n');
+        Ok::<_, std::io::Error>(line)
+    });
+
+    let mut response = Body::from_stream(body_stream).into_response();
+    response.headers_mut().insert(
+        "x-search-id",
+        HeaderValue::from_str(&search_id).expect("search_id is a plain integer string"),
+    );
+    response
+}
+
+/// Cancel an in-flight `/search/stream` request by its search_id.
+async fn search_cancel_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CancelRequest>,
+) -> impl IntoResponse {
+    let found = state
+        .active_searches
+        .lock()
+        .unwrap()
+        .get(&request.search_id)
+        .map(|cancel| cancel.store(true, Ordering::Relaxed))
+        .is_some();
+
+    if found {
+        info!("Cancelled search_id {}", request.search_id);
+        (StatusCode::OK, Json(serde_json::json!({ "status": "cancelled" })))
+    } else {
+        (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": format!("Unknown search_id: {}", request.search_id)
+            })),
+        )
+    }
+}
+
+/// Duplicate-file detection endpoint: groups cached files that share identical content
+async fn duplicates_handler(
+    State(state): State<Arc<AppState>>,
+    Query(request): Query<DuplicatesRequest>,
+) -> impl IntoResponse {
+    let cache = Arc::clone(&*state.cache.read().await);
+    let response = cache.find_duplicates(&request);
+    info!(
+        "Duplicate scan found {} groups across {} files in {}ms",
+        response.groups.len(),
+        response.files_scanned,
+        response.duration_ms
+    );
+    (StatusCode::OK, Json(response))
+}
+
 /// Reload the cache
 async fn reload_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     info!("Reloading cache...");
-    let mut cache = state.cache.write().await;
+    let root = state.cache.read().await.root.clone();
 
-    match MmapCache::new(&cache.root) {
+    match MmapCache::new(&root, state.include_binary) {
         Ok(new_cache) => {
-            *cache = new_cache;
+            let file_count = new_cache.files.len();
+            *state.cache.write().await = Arc::new(new_cache);
             info!("Cache reloaded successfully");
             (
                 StatusCode::OK,
                 Json(serde_json::json!({
                     "status": "reloaded",
-                    "files": cache.files.len()
+                    "files": file_count
                 })),
             )
         }
@@ -325,11 +956,20 @@ async fn main() -> Result<()> {
         anyhow::bail!("Directory does not exist: {}", root_dir.display());
     }
 
+    // Whether to index and search binary files, rather than skipping them like ripgrep
+    // does by default.
+    let include_binary = std::env::var("INCLUDE_BINARY")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+
     // Build the memory-mapped cache
-    let cache = MmapCache::new(&root_dir)?;
+    let cache = MmapCache::new(&root_dir, include_binary)?;
 
     let state = Arc::new(AppState {
-        cache: Arc::new(RwLock::new(cache)),
+        cache: Arc::new(RwLock::new(Arc::new(cache))),
+        active_searches: Mutex::new(HashMap::new()),
+        next_search_id: AtomicU64::new(0),
+        include_binary,
     });
 
     // Build the router
@@ -337,6 +977,9 @@ async fn main() -> Result<()> {
         .route("/health", get(health_check))
         .route("/search", get(search_handler))
         .route("/search", post(search_post_handler))
+        .route("/search/stream", get(search_stream_handler))
+        .route("/search/cancel", post(search_cancel_handler))
+        .route("/duplicates", get(duplicates_handler))
         .route("/reload", post(reload_handler))
         .with_state(state);
 
@@ -350,6 +993,9 @@ async fn main() -> Result<()> {
     info!("  GET  /health - Health check");
     info!("  GET  /search?pattern=<regex>&case_sensitive=<bool>&max_results=<n> - Search");
     info!("  POST /search - Search (JSON body)");
+    info!("  GET  /search/stream?pattern=<regex>&... - Streamed search (NDJSON, cancellable)");
+    info!("  POST /search/cancel - Cancel a streaming search by search_id");
+    info!("  GET  /duplicates?min_group_size=<n>&min_size=<bytes> - Find duplicate files");
     info!("  POST /reload - Reload file cache");
 
     axum::serve(listener, app)
@@ -0,0 +1,366 @@
+//! Trigram inverted index used to prune candidate files before handing them to the
+//! regex engine - the approach behind Google Code Search (see Russ Cox's "Regular
+//! Expression Matching with a Trigram Index" article): index every 3-byte sequence
+//! appearing in each file, then for a query extract the literal substrings any match
+//! must contain and intersect/union the matching posting lists instead of scanning
+//! every file with `grep_searcher` on every request.
+//!
+//! The index is always built over lowercased bytes. That's a deliberate simplification
+//! over maintaining separate case-sensitive and case-insensitive postings: a lowercased
+//! index is always a safe superset for a case-sensitive query too (if the raw text
+//! contains a literal, its lowercased form contains the lowercased literal's trigrams),
+//! so correctness never depends on `case_sensitive` - only the final regex pass, which
+//! still runs against the real file bytes, does.
+
+use memmap2::Mmap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+
+/// Maps each distinct (lowercased) trigram to the sorted list of file ids whose
+/// contents contain it. Built once in `MmapCache::new` and rebuilt on `/reload`; file
+/// ids are positions into the `Vec<(PathBuf, Mmap)>` passed to `build`.
+pub struct TrigramIndex {
+    postings: HashMap<[u8; 3], Vec<u32>>,
+}
+
+impl TrigramIndex {
+    /// Build the index over an ordered list of files, indexed by position.
+    pub fn build(files: &[(PathBuf, Mmap)]) -> Self {
+        let mut postings: HashMap<[u8; 3], Vec<u32>> = HashMap::new();
+
+        for (file_id, (_, mmap)) in files.iter().enumerate() {
+            let mut seen: HashSet<[u8; 3]> = HashSet::new();
+            for window in mmap[..].windows(3) {
+                seen.insert([
+                    window[0].to_ascii_lowercase(),
+                    window[1].to_ascii_lowercase(),
+                    window[2].to_ascii_lowercase(),
+                ]);
+            }
+            for trigram in seen {
+                postings.entry(trigram).or_default().push(file_id as u32);
+            }
+        }
+
+        Self { postings }
+    }
+
+    fn posting(&self, trigram: [u8; 3]) -> BTreeSet<u32> {
+        self.postings
+            .get(&trigram)
+            .map(|ids| ids.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Narrow the set of file ids worth regex-scanning for `pattern`, or `None` if the
+    /// pattern has no mandatory literal substring long enough to extract trigrams from
+    /// (e.g. `.*`, `\d+`, or a literal shorter than 3 bytes) - in which case every file
+    /// still has to be scanned.
+    pub fn candidates(&self, pattern: &str) -> Option<BTreeSet<u32>> {
+        self.resolve(&parse(pattern))
+    }
+
+    fn resolve(&self, req: &Req) -> Option<BTreeSet<u32>> {
+        match req {
+            Req::Any => None,
+            Req::Literal(trigrams) => {
+                let mut set: Option<BTreeSet<u32>> = None;
+                for &trigram in trigrams {
+                    let posting = self.posting(trigram);
+                    set = Some(match set {
+                        None => posting,
+                        Some(acc) => acc.intersection(&posting).copied().collect(),
+                    });
+                }
+                set
+            }
+            // Concatenation: every mandatory factor must hold, so intersect the
+            // constrained ones (an unconstrained factor - `Any` - doesn't narrow
+            // anything and is simply skipped rather than zeroing out the whole AND).
+            Req::And(children) => {
+                let mut result: Option<BTreeSet<u32>> = None;
+                for child in children {
+                    if let Some(set) = self.resolve(child) {
+                        result = Some(match result {
+                            None => set,
+                            Some(acc) => acc.intersection(&set).copied().collect(),
+                        });
+                    }
+                }
+                result
+            }
+            // Alternation: a match only needs to satisfy one branch, so the candidate
+            // set is the union of the branches' sets - unless some branch has no
+            // extractable literal at all, in which case that branch alone could match
+            // any file and the whole alternation can't be pruned.
+            Req::Or(branches) => {
+                let mut union = BTreeSet::new();
+                for branch in branches {
+                    match self.resolve(branch) {
+                        Some(set) => union.extend(set),
+                        None => return None,
+                    }
+                }
+                Some(union)
+            }
+        }
+    }
+}
+
+/// A requirement tree extracted from a regex pattern: what trigram-indexable literal
+/// substrings a match is guaranteed to contain.
+enum Req {
+    /// No mandatory literal could be extracted from this part of the pattern.
+    Any,
+    /// The (already-lowercased) trigrams of one contiguous mandatory literal run.
+    Literal(Vec<[u8; 3]>),
+    /// Concatenation - all of these must hold.
+    And(Vec<Req>),
+    /// Alternation - at least one of these must hold.
+    Or(Vec<Req>),
+}
+
+/// Parse `pattern` into a trigram requirement tree. Operates byte-by-byte rather than
+/// on a full regex AST: deliberately conservative, so every quantifier (`*`, `+`, `?`,
+/// `{m,n}`) drops its preceding atom from consideration rather than trying to tell
+/// "optional" apart from "one or more" - that only costs a missed pruning opportunity
+/// on patterns like `ab+c`, never an incorrect candidate set.
+fn parse(pattern: &str) -> Req {
+    let bytes = pattern.as_bytes();
+    let mut pos = 0;
+    parse_alternation(bytes, &mut pos)
+}
+
+fn parse_alternation(p: &[u8], pos: &mut usize) -> Req {
+    let mut branches = vec![parse_sequence(p, pos)];
+    while p.get(*pos) == Some(&b'|') {
+        *pos += 1;
+        branches.push(parse_sequence(p, pos));
+    }
+    if branches.len() == 1 {
+        branches.into_iter().next().unwrap()
+    } else {
+        Req::Or(branches)
+    }
+}
+
+fn parse_sequence(p: &[u8], pos: &mut usize) -> Req {
+    let mut parts: Vec<Req> = Vec::new();
+    let mut run: Vec<u8> = Vec::new();
+
+    macro_rules! flush {
+        () => {{
+            if run.len() >= 3 {
+                let trigrams: Vec<[u8; 3]> = run
+                    .windows(3)
+                    .map(|w| {
+                        [
+                            w[0].to_ascii_lowercase(),
+                            w[1].to_ascii_lowercase(),
+                            w[2].to_ascii_lowercase(),
+                        ]
+                    })
+                    .collect();
+                parts.push(Req::Literal(trigrams));
+            }
+            run.clear();
+        }};
+    }
+
+    while *pos < p.len() {
+        match p[*pos] {
+            b'|' | b')' => break,
+            // `^`/`$` are zero-width, but they're anchored to line boundaries, not byte
+            // positions in the haystack they're adjacent to in the pattern text: `$`
+            // matches before a `\n` (or end of input), so `foo$bar` requires
+            // `"foo\nbar"`, not the contiguous literal `"foobar"`. Flush the run so its
+            // trigrams aren't extracted across the anchor.
+            b'^' | b'$' => {
+                *pos += 1;
+                flush!();
+            }
+            b'.' => {
+                *pos += 1;
+                flush!();
+                consume_quantifier(p, pos);
+            }
+            b'[' => {
+                *pos += 1;
+                skip_char_class(p, pos);
+                flush!();
+                consume_quantifier(p, pos);
+            }
+            b'(' => {
+                flush!();
+                *pos += 1;
+                let inner = parse_group(p, pos);
+                if !consume_quantifier(p, pos) {
+                    parts.push(inner);
+                }
+            }
+            b'\\' => {
+                *pos += 1;
+                match p.get(*pos).copied() {
+                    None => {}
+                    Some(b'd' | b'D' | b'w' | b'W' | b's' | b'S' | b'b' | b'B') => {
+                        *pos += 1;
+                        flush!();
+                        consume_quantifier(p, pos);
+                    }
+                    // Any other letter escape (`\x41`, `\u{..}`, `\pL`, `\A`, `\z`, ...) does
+                    // not stand for its own character: skip it with its argument and treat
+                    // it as unconstrained.
+                    Some(c) if c.is_ascii_alphanumeric() && !matches!(c, b'n' | b't' | b'r') => {
+                        *pos += 1;
+                        flush!();
+                        if p.get(*pos) == Some(&b'{') {
+                            skip_until(p, pos, b'}');
+                        } else {
+                            let arg_len = match c {
+                                b'x' => 2,
+                                b'u' => 4,
+                                b'U' => 8,
+                                b'p' | b'P' => 1,
+                                _ => 0,
+                            };
+                            *pos = (*pos + arg_len).min(p.len());
+                        }
+                        consume_quantifier(p, pos);
+                    }
+                    Some(c) => {
+                        *pos += 1;
+                        let literal = match c {
+                            b'n' => b'\n',
+                            b't' => b'\t',
+                            b'r' => b'\r',
+                            other => other,
+                        };
+                        if consume_quantifier(p, pos) {
+                            flush!();
+                        } else {
+                            run.push(literal);
+                        }
+                    }
+                }
+            }
+            c => {
+                *pos += 1;
+                if consume_quantifier(p, pos) {
+                    flush!();
+                } else {
+                    run.push(c);
+                }
+            }
+        }
+    }
+    flush!();
+
+    match parts.len() {
+        0 => Req::Any,
+        1 => parts.into_iter().next().unwrap(),
+        _ => Req::And(parts),
+    }
+}
+
+/// Parse the contents of a group, assuming the opening `(` has already been consumed.
+/// Handles `(?:...)` and named groups as plain grouping; anything else starting with
+/// `?` (flags, lookaround, ...) is treated conservatively as unconstrained.
+fn parse_group(p: &[u8], pos: &mut usize) -> Req {
+    if p.get(*pos) == Some(&b'?') {
+        let after_mark = *pos + 1;
+        match p.get(after_mark) {
+            Some(b':') => *pos = after_mark + 1,
+            Some(b'P') if p.get(after_mark + 1) == Some(&b'<') => {
+                *pos = after_mark + 2;
+                skip_until(p, pos, b'>');
+            }
+            Some(b'<') if !matches!(p.get(after_mark + 1), Some(b'=') | Some(b'!')) => {
+                *pos = after_mark + 1;
+                skip_until(p, pos, b'>');
+            }
+            _ => {
+                skip_balanced_group(p, pos);
+                return Req::Any;
+            }
+        }
+    }
+
+    let inner = parse_alternation(p, pos);
+    if p.get(*pos) == Some(&b')') {
+        *pos += 1;
+    }
+    inner
+}
+
+/// Skip to (and past) the `)` matching the `(` whose position directly precedes
+/// `*pos` (i.e. `*pos` is already one character inside the group).
+fn skip_balanced_group(p: &[u8], pos: &mut usize) {
+    let mut depth = 1usize;
+    while *pos < p.len() && depth > 0 {
+        match p[*pos] {
+            b'\\' => {
+                *pos += 2;
+                continue;
+            }
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            _ => {}
+        }
+        *pos += 1;
+    }
+}
+
+fn skip_until(p: &[u8], pos: &mut usize, target: u8) {
+    while *pos < p.len() && p[*pos] != target {
+        *pos += 1;
+    }
+    if *pos < p.len() {
+        *pos += 1;
+    }
+}
+
+fn skip_char_class(p: &[u8], pos: &mut usize) {
+    if p.get(*pos) == Some(&b'^') {
+        *pos += 1;
+    }
+    if p.get(*pos) == Some(&b']') {
+        *pos += 1; // a leading ']' is a literal member of the class
+    }
+    while *pos < p.len() && p[*pos] != b']' {
+        if p[*pos] == b'\\' {
+            *pos += 1;
+        }
+        *pos += 1;
+    }
+    if *pos < p.len() {
+        *pos += 1; // consume the closing ']'
+    }
+}
+
+/// If a quantifier (`*`, `+`, `?`, or `{m,n}`, optionally followed by a non-greedy `?`)
+/// starts at `*pos`, consume it and return `true`.
+fn consume_quantifier(p: &[u8], pos: &mut usize) -> bool {
+    match p.get(*pos) {
+        Some(b'*') | Some(b'+') | Some(b'?') => {
+            *pos += 1;
+            if p.get(*pos) == Some(&b'?') {
+                *pos += 1;
+            }
+            true
+        }
+        Some(b'{') => {
+            *pos += 1;
+            while *pos < p.len() && p[*pos] != b'}' {
+                *pos += 1;
+            }
+            if *pos < p.len() {
+                *pos += 1;
+            }
+            if p.get(*pos) == Some(&b'?') {
+                *pos += 1;
+            }
+            true
+        }
+        _ => false,
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::{Context, Result};
-use curserve::MmapCache;
+use curserve::{MmapCache, SearchMode, SearchRequest, SearchTarget};
 use std::path::PathBuf;
 use std::process::Command;
 use std::time::{Duration, Instant};
@@ -115,8 +115,15 @@ fn main() -> Result<()> {
     );
 
     // Benchmark 1: Memory-mapped search
+    let request = SearchRequest {
+        pattern: pattern.to_string(),
+        mode: SearchMode::Regex,
+        case_sensitive: false,
+        target: SearchTarget::Contents,
+        ..Default::default()
+    };
     let mmap_avg = benchmark("Memory-Mapped Search", iterations, || {
-        cache.search(pattern, false).unwrap().len()
+        cache.search(&request).unwrap().matches.len()
     });
 
     // Benchmark 2: Subprocess ripgrep (if available)
@@ -140,7 +147,7 @@ fn main() -> Result<()> {
         "Cache build time:      {:.2}ms",
         cache_time.as_secs_f64() * 1000.0
     );
-    println!("Files indexed:         {}", cache.files.len());
+    println!("Files indexed:         {}", cache.file_count());
     println!(
         "Memory-mapped search:  {:.2}ms avg",
         mmap_avg.as_secs_f64() * 1000.0
@@ -0,0 +1,258 @@
+use crate::protocol::{ErrorCode, Request, Response};
+use crate::transport::{Conn, Transport};
+use anyhow::{Context, Result};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Options for a `Client::request_ripgrep` call, mirroring `SearchRequest`'s
+/// ripgrep-parity fields
+#[derive(Debug, Clone, Default)]
+pub struct RipgrepQuery {
+    pub pattern: String,
+    pub case_sensitive: bool,
+    pub fixed_strings: bool,
+    pub before_context: usize,
+    pub after_context: usize,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+/// The lines a `request_ripgrep` call streamed back, plus the server's reported total
+pub struct RipgrepResult {
+    pub lines: Vec<String>,
+    pub total_matches: usize,
+}
+
+/// A structured protocol-level failure reported by the service. `Client` methods return
+/// this wrapped in an `anyhow::Error`; downcast with `.downcast_ref::<ClientError>()` to
+/// branch on `code` instead of matching the display message.
+#[derive(Debug)]
+pub struct ClientError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// Demultiplexes frames read off a PID's shared response socket by their `Response::id`,
+/// so several `request_ripgrep` calls can have results in flight on that one connection
+/// at once instead of needing a response socket per search. A background thread owns
+/// the socket and reads frames in a loop, routing each to whichever caller registered
+/// that frame's id; callers never touch the socket directly.
+struct Dispatcher {
+    pending: Arc<Mutex<HashMap<u64, Sender<Response>>>>,
+    _reader: thread::JoinHandle<()>,
+}
+
+impl Dispatcher {
+    fn spawn(mut response_conn: Conn) -> Self {
+        let pending: Arc<Mutex<HashMap<u64, Sender<Response>>>> = Arc::new(Mutex::new(HashMap::new()));
+        let reader_pending = Arc::clone(&pending);
+
+        let reader = thread::spawn(move || {
+            loop {
+                let response: Response = match read_json_line(&mut response_conn) {
+                    Ok(response) => response,
+                    Err(_) => break,
+                };
+                let sender = lock(&reader_pending).get(&response.id).cloned();
+                if let Some(sender) = sender {
+                    let _ = sender.send(response);
+                }
+            }
+            // The connection is gone; drop every still-registered sender so a caller
+            // blocked in `recv` sees a disconnected channel instead of hanging forever.
+            lock(&reader_pending).clear();
+        });
+
+        Self {
+            pending,
+            _reader: reader,
+        }
+    }
+
+    /// Register interest in frames carrying `id`, returning the receiving end callers
+    /// should read their response frames from.
+    fn register(&self, id: u64) -> Receiver<Response> {
+        let (tx, rx) = unbounded();
+        lock(&self.pending).insert(id, tx);
+        rx
+    }
+
+    fn unregister(&self, id: u64) {
+        lock(&self.pending).remove(&id);
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A typed client for the search service's socket protocol, so callers don't have to
+/// hand-build the JSON `Request`/`Response` frames themselves.
+///
+/// `alloc_pid` must be called once before `request_ripgrep`, since it's what opens the
+/// per-PID response connection (and its demultiplexing [`Dispatcher`]) that streamed
+/// results arrive on. After that, `request_ripgrep` takes `&self`, so multiple searches
+/// for the same PID can be issued concurrently (e.g. from an `Arc<Client>` shared across
+/// threads) and each call's frames are correlated back to it by `Request`/`Response::id`.
+pub struct Client {
+    transport: Transport,
+    request_conn: Mutex<Conn>,
+    dispatcher: Option<Dispatcher>,
+    next_id: AtomicU64,
+}
+
+impl Client {
+    /// Connect to a service already listening on `transport`'s request socket.
+    pub fn connect(transport: Transport) -> Result<Self> {
+        let request_conn = transport
+            .connect()
+            .context("Failed to connect to request socket")?;
+
+        Ok(Self {
+            transport,
+            request_conn: Mutex::new(request_conn),
+            dispatcher: None,
+            next_id: AtomicU64::new(0),
+        })
+    }
+
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Allocate a codebase for `pid`, then connect to the service's response socket for
+    /// it so later `request_ripgrep` calls have somewhere to read streamed matches from.
+    pub fn alloc_pid(&mut self, pid: u32, repo_dir_path: &str) -> Result<String> {
+        let id = self.next_id();
+        let request = Request::AllocPid {
+            id,
+            pid,
+            repo_dir_path: repo_dir_path.to_string(),
+        };
+
+        let response: Response = {
+            let mut request_conn = lock(&self.request_conn);
+            send_json_line(&mut request_conn, &request)?;
+            read_json_line(&mut request_conn)?
+        };
+        if response.response_status != 1 {
+            return Err(client_error(response, "alloc_pid failed"));
+        }
+
+        // For TCP, the service bound the response listener on an ephemeral port and
+        // reports the real port here; for Unix transports the response path is
+        // derived from the PID alone and there's nothing to override.
+        let response_transport = match response.response_port {
+            Some(port) => Transport::Tcp(port),
+            None => self.transport.response_transport(pid),
+        };
+        let response_conn = response_transport
+            .connect()
+            .context("Failed to connect to response socket")?;
+        self.dispatcher = Some(Dispatcher::spawn(response_conn));
+
+        Ok(response.text.unwrap_or_default())
+    }
+
+    /// Run a ripgrep-style search against `pid`'s allocated codebase, draining the
+    /// service's streamed `chunk`/`done` frames into one result.
+    ///
+    /// Can be called concurrently (it only needs `&self`): each call registers its own
+    /// request id with the response [`Dispatcher`] before sending, so replies to
+    /// different in-flight searches on the same response socket don't get crossed.
+    pub fn request_ripgrep(&self, pid: u32, query: RipgrepQuery) -> Result<RipgrepResult> {
+        let dispatcher = self
+            .dispatcher
+            .as_ref()
+            .context("alloc_pid must be called before request_ripgrep")?;
+
+        let id = self.next_id();
+        let request = Request::RequestRipgrep {
+            id,
+            pid,
+            pattern: query.pattern,
+            case_sensitive: query.case_sensitive,
+            fixed_strings: query.fixed_strings,
+            before_context: query.before_context,
+            after_context: query.after_context,
+            include: query.include,
+            exclude: query.exclude,
+        };
+
+        let responses = dispatcher.register(id);
+        send_json_line(&mut lock(&self.request_conn), &request)?;
+
+        let mut lines = Vec::new();
+        loop {
+            let response = responses
+                .recv()
+                .context("Connection closed before a full response was received")?;
+
+            if response.response_status != 1 {
+                dispatcher.unregister(id);
+                return Err(client_error(response, "request_ripgrep failed"));
+            }
+
+            if let Some(text) = response.text {
+                lines.extend(text.lines().map(|l| l.to_string()));
+            }
+
+            if response.done {
+                dispatcher.unregister(id);
+                return Ok(RipgrepResult {
+                    lines,
+                    total_matches: response.total_matches.unwrap_or(0),
+                });
+            }
+        }
+    }
+}
+
+/// Build a `ClientError` from a failure `Response`, falling back to `ErrorCode::BadRequest`
+/// only if the server didn't send a `code` at all (e.g. an older server that predates
+/// structured error codes) - this service always sends one.
+fn client_error(response: Response, default_message: &str) -> anyhow::Error {
+    ClientError {
+        code: response.code.unwrap_or(ErrorCode::BadRequest),
+        message: response.error.unwrap_or_else(|| default_message.to_string()),
+    }
+    .into()
+}
+
+/// Serialize `value` as one newline-delimited JSON frame
+fn send_json_line<T: serde::Serialize>(conn: &mut Conn, value: &T) -> Result<()> {
+    let json = serde_json::to_string(value)?;
+    conn.write_all(json.as_bytes())?;
+    conn.write_all(b"\n")?;
+    conn.flush()?;
+    Ok(())
+}
+
+/// Read one newline-delimited JSON frame and deserialize it
+fn read_json_line<T: serde::de::DeserializeOwned>(conn: &mut Conn) -> Result<T> {
+    let mut buf = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        let n = conn.read(&mut byte)?;
+        if n == 0 {
+            anyhow::bail!("Connection closed before a full response was received");
+        }
+        if byte[0] == b'\n' {
+            break;
+        }
+        buf.push(byte[0]);
+    }
+    serde_json::from_slice(&buf).context("Failed to parse response JSON")
+}
@@ -1,26 +1,289 @@
 use anyhow::{Context, Result};
+use crossbeam_channel::{bounded, Receiver};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use grep_matcher::Matcher;
 use grep_regex::RegexMatcherBuilder;
 use grep_searcher::sinks::UTF8;
 use grep_searcher::Searcher;
 use memmap2::Mmap;
 use rayon::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use twox_hash::xxh3::hash128;
 
-/// Memory-mapped file cache for a single codebase
+mod client;
+mod protocol;
+mod simhash;
+mod transport;
+use simhash::BkTree;
+
+pub use client::{Client, ClientError, RipgrepQuery, RipgrepResult};
+pub use protocol::{ErrorCode, Request, Response};
+pub use transport::{Conn, Listener, Transport};
+
+/// Default capacity of the channel used by `MmapCache::search_streaming`
+const STREAM_CHANNEL_CAPACITY: usize = 256;
+
+/// Fuzzy-match relevance score a line must exceed to be considered a hit
+const FUZZY_SCORE_THRESHOLD: i64 = 0;
+
+/// How a search pattern should be matched against line contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Literal/regex matching via the `grep` crates
+    Regex,
+    /// Typo-tolerant subsequence matching via `fuzzy_matcher`'s `SkimMatcherV2`
+    Fuzzy,
+}
+
+/// Which part of the cache a search should run against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchTarget {
+    /// Match inside file bodies only (the default)
+    Contents,
+    /// Match relative file paths only, e.g. for a quick-open panel
+    Names,
+    /// Match both file bodies and relative file paths
+    Both,
+}
+
+/// Parameters for a `MmapCache::search` call
+#[derive(Debug, Clone)]
+pub struct SearchRequest {
+    pub pattern: String,
+    pub mode: SearchMode,
+    pub case_sensitive: bool,
+    pub target: SearchTarget,
+    /// Treat `pattern` as a literal string rather than a regex (ripgrep's
+    /// `--fixed-strings`). Only meaningful when `mode` is `Regex`.
+    pub fixed_strings: bool,
+    /// Lines of context to include before/after each match (ripgrep's `-B`/`-A`)
+    pub before_context: usize,
+    pub after_context: usize,
+    /// Only search paths matching at least one of these globs (ripgrep's `-g GLOB`).
+    /// Empty means no restriction.
+    pub include_globs: Vec<String>,
+    /// Skip paths matching any of these globs (ripgrep's `-g '!GLOB'`)
+    pub exclude_globs: Vec<String>,
+}
+
+impl Default for SearchRequest {
+    fn default() -> Self {
+        Self {
+            pattern: String::new(),
+            mode: SearchMode::Regex,
+            case_sensitive: false,
+            target: SearchTarget::Contents,
+            fixed_strings: false,
+            before_context: 0,
+            after_context: 0,
+            include_globs: Vec::new(),
+            exclude_globs: Vec::new(),
+        }
+    }
+}
+
+/// A single search match
+#[derive(Debug, Clone)]
+pub struct SearchMatch {
+    pub path: String,
+    pub line_number: u64,
+    pub line: String,
+    /// Fuzzy relevance score (0 for regex matches, which aren't ranked)
+    pub score: i64,
+    /// Lines immediately preceding the match, oldest first (empty unless requested via
+    /// `SearchRequest::before_context`)
+    pub before: Vec<String>,
+    /// Lines immediately following the match (empty unless requested via
+    /// `SearchRequest::after_context`)
+    pub after: Vec<String>,
+}
+
+/// A file path matched by name rather than by content
+#[derive(Debug, Clone)]
+pub struct PathMatch {
+    pub path: String,
+    /// Fuzzy relevance score (0 for regex matches, which aren't ranked)
+    pub score: i64,
+}
+
+/// Result of a `MmapCache::search` call
+#[derive(Debug, Clone)]
+pub struct SearchResponse {
+    pub matches: Vec<SearchMatch>,
+    /// File paths matched by name; populated when the request's `target` includes names
+    pub path_matches: Vec<PathMatch>,
+}
+
+/// Memory-mapped file cache for a single codebase.
+///
+/// Byte-identical files (lockfiles, generated code, vendored copies) are mapped once
+/// and shared: `blobs` holds one `Mmap` per distinct content hash, while
+/// `paths_by_hash`/`hash_by_path` track which paths share which blob so a search only
+/// scans each unique blob a single time.
 pub struct MmapCache {
-    pub files: HashMap<PathBuf, Mmap>,
+    blobs: HashMap<u128, Mmap>,
+    paths_by_hash: HashMap<u128, Vec<PathBuf>>,
+    hash_by_path: HashMap<PathBuf, u128>,
     pub root: PathBuf,
+
+    /// BK-tree over every indexed line's SimHash fingerprint, for near-duplicate lookup
+    simhash_index: BkTree,
+    /// Side table of indexed lines; tombstoned (set to `None`) on removal rather than
+    /// shifted, so `BkTree` item ids stay stable
+    line_entries: Vec<Option<LineEntry>>,
+    /// Which `line_entries` ids a given path contributed, for removal on reload/remove
+    entries_by_path: HashMap<PathBuf, Vec<usize>>,
+
+    /// Cached results of previous `search` calls, keyed by pattern string, so typing an
+    /// extension of an earlier query can filter the cached matches instead of
+    /// rescanning every file
+    query_cache: Mutex<HashMap<String, CachedQuery>>,
+}
+
+/// A previous `search` call's results, kept around for prefix-extension reuse
+struct CachedQuery {
+    mode: SearchMode,
+    case_sensitive: bool,
+    matches: Vec<SearchMatch>,
+    /// Absolute paths that contributed to `matches`, so `reload_file`/`remove_file`
+    /// can drop only the cache entries they actually affect
+    paths: HashSet<PathBuf>,
+}
+
+/// A single line indexed for near-duplicate ("similar line") lookup
+struct LineEntry {
+    path: String,
+    line_number: u64,
+    line: String,
+    fingerprint: u64,
+}
+
+/// Compute a fast, non-cryptographic content hash used to dedupe identical files
+fn hash_bytes(data: &[u8]) -> u128 {
+    hash128(data)
+}
+
+/// Regex pattern to actually compile for a request: `pattern` verbatim, unless
+/// `fixed_strings` asks for it to be matched literally (ripgrep's `--fixed-strings`),
+/// in which case regex metacharacters are escaped first.
+fn effective_pattern(request: &SearchRequest) -> String {
+    if request.fixed_strings {
+        escape_regex(&request.pattern)
+    } else {
+        request.pattern.clone()
+    }
+}
+
+/// Escape regex metacharacters so the returned string matches `s` literally.
+fn escape_regex(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(
+            c,
+            '\\' | '.' | '+' | '*' | '?' | '(' | ')' | '|' | '[' | ']' | '{' | '}' | '^' | '$'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// True if `s` contains none of the characters `escape_regex` would need to escape,
+/// i.e. it means exactly the same thing as a regex as it does as a literal substring.
+fn is_plain_literal(s: &str) -> bool {
+    !s.chars().any(|c| {
+        matches!(
+            c,
+            '\\' | '.' | '+' | '*' | '?' | '(' | ')' | '|' | '[' | ']' | '{' | '}' | '^' | '$'
+        )
+    })
+}
+
+/// Lines immediately before/after `line_number` (1-indexed), clamped to `lines`'
+/// bounds, for `SearchRequest::before_context`/`after_context`.
+fn context_slice(
+    lines: &[&str],
+    line_number: u64,
+    before: usize,
+    after: usize,
+) -> (Vec<String>, Vec<String>) {
+    let idx = (line_number - 1) as usize;
+    if idx >= lines.len() {
+        return (Vec::new(), Vec::new());
+    }
+
+    let start = idx.saturating_sub(before);
+    let before_lines = lines[start..idx].iter().map(|s| s.to_string()).collect();
+
+    let end = (idx + 1 + after).min(lines.len());
+    let after_lines = lines[idx + 1..end].iter().map(|s| s.to_string()).collect();
+
+    (before_lines, after_lines)
+}
+
+/// Path-glob filter built from `SearchRequest::include_globs`/`exclude_globs`
+/// (ripgrep's `-g GLOB`/`-g '!GLOB'`). An empty filter allows every path.
+struct GlobFilter {
+    include: Option<globset::GlobSet>,
+    exclude: Option<globset::GlobSet>,
+}
+
+impl GlobFilter {
+    fn new(include_globs: &[String], exclude_globs: &[String]) -> Result<Self> {
+        let build = |globs: &[String]| -> Result<Option<globset::GlobSet>> {
+            if globs.is_empty() {
+                return Ok(None);
+            }
+
+            let mut builder = globset::GlobSetBuilder::new();
+            for pattern in globs {
+                builder.add(
+                    globset::Glob::new(pattern)
+                        .with_context(|| format!("Invalid glob pattern: {}", pattern))?,
+                );
+            }
+            Ok(Some(builder.build().context("Failed to build glob set")?))
+        };
+
+        Ok(Self {
+            include: build(include_globs)?,
+            exclude: build(exclude_globs)?,
+        })
+    }
+
+    /// Whether `rel_path` passes this filter: it matches at least one include glob (if
+    /// any were given) and no exclude glob.
+    fn allows(&self, rel_path: &str) -> bool {
+        if let Some(include) = &self.include {
+            if !include.is_match(rel_path) {
+                return false;
+            }
+        }
+        if let Some(exclude) = &self.exclude {
+            if exclude.is_match(rel_path) {
+                return false;
+            }
+        }
+        true
+    }
 }
 
 impl MmapCache {
     /// Create a new cache by memory-mapping all files in the given directory
     pub fn new(root: &Path) -> Result<Self> {
         println!("Loading files into memory from: {}", root.display());
-        let mut files = HashMap::new();
+        let mut blobs: HashMap<u128, Mmap> = HashMap::new();
+        let mut paths_by_hash: HashMap<u128, Vec<PathBuf>> = HashMap::new();
+        let mut hash_by_path: HashMap<PathBuf, u128> = HashMap::new();
         let mut file_count = 0;
-        let mut total_bytes = 0u64;
+        let mut unique_bytes = 0u64;
 
         // Use ignore crate to walk directory, respecting .gitignore
         let walker = ignore::WalkBuilder::new(root)
@@ -63,9 +326,18 @@ impl MmapCache {
 
                     match unsafe { Mmap::map(&file) } {
                         Ok(mmap) => {
+                            let hash = hash_bytes(&mmap[..]);
                             file_count += 1;
-                            total_bytes += file_size;
-                            files.insert(path.to_owned(), mmap);
+
+                            if let std::collections::hash_map::Entry::Vacant(entry) =
+                                blobs.entry(hash)
+                            {
+                                unique_bytes += file_size;
+                                entry.insert(mmap);
+                            }
+
+                            paths_by_hash.entry(hash).or_default().push(path.to_owned());
+                            hash_by_path.insert(path.to_owned(), hash);
                         }
                         Err(_) => continue,
                     }
@@ -75,15 +347,128 @@ impl MmapCache {
         }
 
         println!(
-            "Loaded {} files ({:.2} MB total) into memory",
+            "Loaded {} files ({} unique, {:.2} MB unique content) into memory",
             file_count,
-            total_bytes as f64 / 1024.0 / 1024.0
+            blobs.len(),
+            unique_bytes as f64 / 1024.0 / 1024.0
         );
 
-        Ok(Self {
-            files,
+        let mut cache = Self {
+            blobs,
+            paths_by_hash,
+            hash_by_path,
             root: root.to_owned(),
-        })
+            simhash_index: BkTree::new(),
+            line_entries: Vec::new(),
+            entries_by_path: HashMap::new(),
+            query_cache: Mutex::new(HashMap::new()),
+        };
+
+        let indexed_paths: Vec<(PathBuf, u128)> = cache
+            .hash_by_path
+            .iter()
+            .map(|(path, hash)| (path.clone(), *hash))
+            .collect();
+        for (path, hash) in indexed_paths {
+            cache.index_path_lines(&path, hash);
+        }
+
+        Ok(cache)
+    }
+
+    /// Total number of indexed paths (not deduplicated blobs)
+    pub fn file_count(&self) -> usize {
+        self.hash_by_path.len()
+    }
+
+    /// Drop every path whose (possibly non-canonical) form resolves to `path`, freeing
+    /// its blob once no other path references it. Returns the removed path keys.
+    fn drop_path_entries(&mut self, path: &Path) -> Vec<PathBuf> {
+        let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+        let keys_to_remove: Vec<PathBuf> = self
+            .hash_by_path
+            .keys()
+            .filter(|key| {
+                key.canonicalize().unwrap_or_else(|_| (*key).clone()) == canonical_path
+            })
+            .cloned()
+            .collect();
+
+        for key in &keys_to_remove {
+            if let Some(hash) = self.hash_by_path.remove(key) {
+                if let Some(paths) = self.paths_by_hash.get_mut(&hash) {
+                    paths.retain(|p| p != key);
+                    if paths.is_empty() {
+                        self.paths_by_hash.remove(&hash);
+                        self.blobs.remove(&hash);
+                    }
+                }
+            }
+
+            if let Some(ids) = self.entries_by_path.remove(key) {
+                for id in ids {
+                    if let Some(entry) = self.line_entries.get_mut(id).and_then(Option::take) {
+                        self.simhash_index.remove(entry.fingerprint, id);
+                    }
+                }
+            }
+
+            self.query_cache
+                .lock()
+                .unwrap()
+                .retain(|_, cached| !cached.paths.contains(key));
+        }
+
+        keys_to_remove
+    }
+
+    /// Compute and store SimHash fingerprints for every line of `path`'s current
+    /// content, so `find_similar` can find near-duplicates of it
+    fn index_path_lines(&mut self, path: &Path, hash: u128) {
+        let Some(mmap) = self.blobs.get(&hash) else {
+            return;
+        };
+        let Ok(text) = std::str::from_utf8(&mmap[..]) else {
+            return;
+        };
+
+        let rel_path = path
+            .strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string();
+
+        let mut ids = Vec::new();
+        for (idx, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let fingerprint = simhash::simhash(line);
+            let id = self.line_entries.len();
+            self.line_entries.push(Some(LineEntry {
+                path: rel_path.clone(),
+                line_number: (idx + 1) as u64,
+                line: line.to_string(),
+                fingerprint,
+            }));
+            self.simhash_index.insert(fingerprint, id);
+            ids.push(id);
+        }
+
+        self.entries_by_path.insert(path.to_owned(), ids);
+    }
+
+    /// Find lines similar (not necessarily identical) to `line`, within `max_distance`
+    /// bits of Hamming distance between SimHash fingerprints
+    pub fn find_similar(&self, line: &str, max_distance: u32) -> Vec<(String, u64, String)> {
+        let fingerprint = simhash::simhash(line);
+        self.simhash_index
+            .query(fingerprint, max_distance)
+            .into_iter()
+            .filter_map(|id| self.line_entries.get(id)?.as_ref())
+            .map(|entry| (entry.path.clone(), entry.line_number, entry.line.clone()))
+            .collect()
     }
 
     /// Add or reload a single file in the cache
@@ -105,21 +490,9 @@ impl MmapCache {
             }
         }
 
-        // Find and remove any existing entries for this file
+        // Remove any existing entries for this file
         // (handles case where path format differs between initial load and file watcher)
-        let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_owned());
-        let mut keys_to_remove = Vec::new();
-
-        for key in self.files.keys() {
-            let key_canonical = key.canonicalize().unwrap_or_else(|_| key.clone());
-            if key_canonical == canonical_path {
-                keys_to_remove.push(key.clone());
-            }
-        }
-
-        for key in keys_to_remove {
-            self.files.remove(&key);
-        }
+        self.drop_path_entries(path);
 
         match File::open(path) {
             Ok(file) => {
@@ -134,7 +507,14 @@ impl MmapCache {
                 match unsafe { Mmap::map(&file) } {
                     Ok(mmap) => {
                         println!("[FileWatch] Reloaded: {}", path.display());
-                        self.files.insert(path.to_owned(), mmap);
+                        let hash = hash_bytes(&mmap[..]);
+                        self.blobs.entry(hash).or_insert(mmap);
+                        self.paths_by_hash
+                            .entry(hash)
+                            .or_default()
+                            .push(path.to_owned());
+                        self.hash_by_path.insert(path.to_owned(), hash);
+                        self.index_path_lines(path, hash);
                         Ok(())
                     }
                     Err(e) => Err(anyhow::anyhow!("Failed to mmap file: {}", e)),
@@ -149,49 +529,298 @@ impl MmapCache {
 
     /// Remove a file from the cache
     pub fn remove_file(&mut self, path: &Path) {
-        // Find all entries that match this file canonically
-        let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_owned());
-        let mut keys_to_remove = Vec::new();
+        for key in self.drop_path_entries(path) {
+            println!("[FileWatch] Removed: {}", key.display());
+        }
+    }
 
-        for key in self.files.keys() {
-            let key_canonical = key.canonicalize().unwrap_or_else(|_| key.clone());
-            if key_canonical == canonical_path {
-                keys_to_remove.push(key.clone());
+    /// Search all memory-mapped files according to the request's mode and target
+    pub fn search(&self, request: &SearchRequest) -> Result<SearchResponse> {
+        let matches = if matches!(request.target, SearchTarget::Names) {
+            Vec::new()
+        } else if let Some(cached) = self.try_prefix_cache(request)? {
+            cached
+        } else {
+            let matches = match request.mode {
+                SearchMode::Regex => self.search_regex(request)?,
+                SearchMode::Fuzzy => self.search_fuzzy(request)?,
+            };
+            self.cache_query(request, &matches);
+            matches
+        };
+
+        let path_matches = if matches!(request.target, SearchTarget::Contents) {
+            Vec::new()
+        } else {
+            self.search_paths(&request.pattern, request.mode, request.case_sensitive)?
+        };
+
+        Ok(SearchResponse {
+            matches,
+            path_matches,
+        })
+    }
+
+    /// Match relative file paths rather than file contents, e.g. for a file-tree
+    /// quick-open panel. Uses the same regex/fuzzy matchers as content search.
+    pub fn search_paths(
+        &self,
+        pattern: &str,
+        mode: SearchMode,
+        case_sensitive: bool,
+    ) -> Result<Vec<PathMatch>> {
+        match mode {
+            SearchMode::Regex => {
+                let matcher = RegexMatcherBuilder::new()
+                    .case_insensitive(!case_sensitive)
+                    .build(pattern)
+                    .context("Invalid regex pattern")?;
+
+                Ok(self
+                    .hash_by_path
+                    .keys()
+                    .filter_map(|path| {
+                        let rel_path = path
+                            .strip_prefix(&self.root)
+                            .unwrap_or(path)
+                            .to_string_lossy()
+                            .to_string();
+
+                        matcher
+                            .is_match(rel_path.as_bytes())
+                            .unwrap_or(false)
+                            .then_some(PathMatch {
+                                path: rel_path,
+                                score: 0,
+                            })
+                    })
+                    .collect())
+            }
+            SearchMode::Fuzzy => {
+                let fuzzy = SkimMatcherV2::default();
+                let needle = if case_sensitive {
+                    pattern.to_string()
+                } else {
+                    pattern.to_lowercase()
+                };
+
+                let mut matches: Vec<PathMatch> = self
+                    .hash_by_path
+                    .keys()
+                    .filter_map(|path| {
+                        let rel_path = path
+                            .strip_prefix(&self.root)
+                            .unwrap_or(path)
+                            .to_string_lossy()
+                            .to_string();
+                        let haystack = if case_sensitive {
+                            rel_path.clone()
+                        } else {
+                            rel_path.to_lowercase()
+                        };
+
+                        fuzzy
+                            .fuzzy_match(&haystack, &needle)
+                            .filter(|&score| score > FUZZY_SCORE_THRESHOLD)
+                            .map(|score| PathMatch {
+                                path: rel_path,
+                                score,
+                            })
+                    })
+                    .collect();
+                matches.sort_by(|a, b| b.score.cmp(&a.score));
+                Ok(matches)
             }
         }
+    }
 
-        for key in keys_to_remove {
-            self.files.remove(&key);
-            println!("[FileWatch] Removed: {}", key.display());
+    /// If a cached result exists for a literal prefix/substring of this query in the
+    /// same mode, filter it down to the narrower pattern instead of rescanning every
+    /// file. Returns `None` when no usable cache entry exists.
+    ///
+    /// Cached entries were computed with no context lines or glob filtering (see
+    /// `cache_query`), so a request asking for either always falls through to a
+    /// fresh scan rather than risk reusing results built under different filters.
+    fn try_prefix_cache(&self, request: &SearchRequest) -> Result<Option<Vec<SearchMatch>>> {
+        if request.fixed_strings
+            || request.before_context > 0
+            || request.after_context > 0
+            || !request.include_globs.is_empty()
+            || !request.exclude_globs.is_empty()
+        {
+            return Ok(None);
         }
+
+        // Substring-containment of the pattern text only implies subset-of-matches
+        // when both patterns are matched literally. For `Regex` mode that's only
+        // true if neither pattern uses any regex metacharacters (e.g. `"foo|bar"`
+        // contains `"foo"` as text but matches a disjoint set of lines), so regex
+        // reuse is restricted to plain-literal patterns; fuzzy reuse has no such
+        // hazard since it always scores the raw text.
+        let base = {
+            let cache = self.query_cache.lock().unwrap();
+            cache
+                .iter()
+                .filter(|(pattern, cached)| {
+                    cached.mode == request.mode
+                        && cached.case_sensitive == request.case_sensitive
+                        && pattern.as_str() != request.pattern
+                        && request.pattern.contains(pattern.as_str())
+                        && (request.mode != SearchMode::Regex
+                            || (is_plain_literal(pattern) && is_plain_literal(&request.pattern)))
+                })
+                .max_by_key(|(pattern, _)| pattern.len())
+                .map(|(_, cached)| cached.matches.clone())
+        };
+
+        let Some(base) = base else {
+            return Ok(None);
+        };
+
+        let filtered = match request.mode {
+            SearchMode::Regex => {
+                let matcher = RegexMatcherBuilder::new()
+                    .case_insensitive(!request.case_sensitive)
+                    .build(&request.pattern)
+                    .context("Invalid regex pattern")?;
+
+                base.into_iter()
+                    .filter(|m| matcher.is_match(m.line.as_bytes()).unwrap_or(false))
+                    .collect()
+            }
+            SearchMode::Fuzzy => {
+                let fuzzy = SkimMatcherV2::default();
+                let needle = if request.case_sensitive {
+                    request.pattern.clone()
+                } else {
+                    request.pattern.to_lowercase()
+                };
+
+                let mut filtered: Vec<SearchMatch> = base
+                    .into_iter()
+                    .filter_map(|m| {
+                        let haystack = if request.case_sensitive {
+                            m.line.clone()
+                        } else {
+                            m.line.to_lowercase()
+                        };
+
+                        fuzzy
+                            .fuzzy_match(&haystack, &needle)
+                            .filter(|&score| score > FUZZY_SCORE_THRESHOLD)
+                            .map(|score| SearchMatch { score, ..m })
+                    })
+                    .collect();
+                filtered.sort_by(|a, b| b.score.cmp(&a.score));
+                filtered
+            }
+        };
+
+        Ok(Some(filtered))
     }
 
-    /// Search all memory-mapped files for the given pattern
-    pub fn search(&self, pattern: &str, case_sensitive: bool) -> Result<Vec<(String, u64, String)>> {
-        let matcher = RegexMatcherBuilder::new()
-            .case_insensitive(!case_sensitive)
-            .build(pattern)
-            .context("Invalid regex pattern")?;
+    /// Remember this query's results so a later, narrower query can reuse them. Only
+    /// results computed without fixed-strings, context lines or glob filtering are kept.
+    fn cache_query(&self, request: &SearchRequest, matches: &[SearchMatch]) {
+        if request.fixed_strings
+            || request.before_context > 0
+            || request.after_context > 0
+            || !request.include_globs.is_empty()
+            || !request.exclude_globs.is_empty()
+        {
+            return;
+        }
 
-        // Search all files in parallel
-        let all_matches: Vec<Vec<(String, u64, String)>> = self
-            .files
-            .par_iter()
-            .map(|(path, mmap)| {
-                let mut matches = Vec::new();
-                let mut searcher = Searcher::new();
+        let paths: HashSet<PathBuf> = matches.iter().map(|m| self.root.join(&m.path)).collect();
+
+        self.query_cache.lock().unwrap().insert(
+            request.pattern.clone(),
+            CachedQuery {
+                mode: request.mode,
+                case_sensitive: request.case_sensitive,
+                matches: matches.to_vec(),
+                paths,
+            },
+        );
+    }
+
+    /// Expand one matched line of a blob into a `SearchMatch` for every path sharing the
+    /// blob's hash that passes `globs`, attaching the same before/after context lines to each.
+    fn fan_out(
+        &self,
+        hash: &u128,
+        line_number: u64,
+        line: &str,
+        score: i64,
+        before: &[String],
+        after: &[String],
+        globs: &GlobFilter,
+    ) -> Vec<SearchMatch> {
+        let Some(paths) = self.paths_by_hash.get(hash) else {
+            return Vec::new();
+        };
 
+        paths
+            .iter()
+            .filter_map(|path| {
                 let rel_path = path
                     .strip_prefix(&self.root)
                     .unwrap_or(path)
                     .to_string_lossy()
                     .to_string();
 
+                globs.allows(&rel_path).then(|| SearchMatch {
+                    path: rel_path,
+                    line_number,
+                    line: line.to_string(),
+                    score,
+                    before: before.to_vec(),
+                    after: after.to_vec(),
+                })
+            })
+            .collect()
+    }
+
+    /// Search all unique file contents for a literal/regex pattern, honoring the
+    /// request's context-line and glob-filter options
+    fn search_regex(&self, request: &SearchRequest) -> Result<Vec<SearchMatch>> {
+        let matcher = RegexMatcherBuilder::new()
+            .case_insensitive(!request.case_sensitive)
+            .build(&effective_pattern(request))
+            .context("Invalid regex pattern")?;
+        let globs = GlobFilter::new(&request.include_globs, &request.exclude_globs)?;
+
+        // Search each unique blob once in parallel, then fan each hit out to every
+        // path that shares its content
+        let all_matches: Vec<Vec<SearchMatch>> = self
+            .blobs
+            .par_iter()
+            .map(|(hash, mmap)| {
+                let mut matches = Vec::new();
+                let mut searcher = Searcher::new();
+                let lines: Vec<&str> = std::str::from_utf8(&mmap[..])
+                    .map(|text| text.lines().collect())
+                    .unwrap_or_default();
+
                 let _ = searcher.search_slice(
                     &matcher,
                     &mmap[..],
                     UTF8(|line_num, line| {
-                        matches.push((rel_path.clone(), line_num, line.trim_end().to_string()));
+                        let (before, after) = context_slice(
+                            &lines,
+                            line_num,
+                            request.before_context,
+                            request.after_context,
+                        );
+                        matches.extend(self.fan_out(
+                            hash,
+                            line_num,
+                            line.trim_end(),
+                            0,
+                            &before,
+                            &after,
+                            &globs,
+                        ));
                         Ok(true)
                     }),
                 );
@@ -202,4 +831,298 @@ impl MmapCache {
 
         Ok(all_matches.into_iter().flatten().collect())
     }
+
+    /// Typo-tolerant search over unique line contents, ranked by relevance score,
+    /// honoring the request's context-line and glob-filter options
+    fn search_fuzzy(&self, request: &SearchRequest) -> Result<Vec<SearchMatch>> {
+        let matcher = SkimMatcherV2::default();
+        let needle = if request.case_sensitive {
+            request.pattern.clone()
+        } else {
+            request.pattern.to_lowercase()
+        };
+        let globs = GlobFilter::new(&request.include_globs, &request.exclude_globs)?;
+
+        let all_matches: Vec<Vec<SearchMatch>> = self
+            .blobs
+            .par_iter()
+            .map(|(hash, mmap)| {
+                let mut matches = Vec::new();
+
+                let Ok(text) = std::str::from_utf8(&mmap[..]) else {
+                    return matches;
+                };
+                let lines: Vec<&str> = text.lines().collect();
+
+                for (idx, line) in lines.iter().enumerate() {
+                    let haystack = if request.case_sensitive {
+                        line.to_string()
+                    } else {
+                        line.to_lowercase()
+                    };
+
+                    if let Some(score) = matcher.fuzzy_match(&haystack, &needle) {
+                        if score > FUZZY_SCORE_THRESHOLD {
+                            let line_number = (idx + 1) as u64;
+                            let (before, after) = context_slice(
+                                &lines,
+                                line_number,
+                                request.before_context,
+                                request.after_context,
+                            );
+                            matches.extend(self.fan_out(
+                                hash, line_number, line, score, &before, &after, &globs,
+                            ));
+                        }
+                    }
+                }
+
+                matches
+            })
+            .collect();
+
+        let mut matches: Vec<SearchMatch> = all_matches.into_iter().flatten().collect();
+        matches.sort_by(|a, b| b.score.cmp(&a.score));
+        Ok(matches)
+    }
+
+    /// Search all memory-mapped files, streaming matches over a channel as each file
+    /// finishes instead of collecting them all before returning.
+    ///
+    /// The scan runs on a background thread so the returned `Receiver` can be drained
+    /// incrementally. Set `cancel` to stop an in-flight query early; pass a `deadline`
+    /// to abort remaining work once that much time has elapsed since the call started.
+    pub fn search_streaming(
+        self: Arc<Self>,
+        request: SearchRequest,
+        cancel: Arc<AtomicBool>,
+        deadline: Option<Duration>,
+    ) -> Receiver<SearchMatch> {
+        let (tx, rx) = bounded(STREAM_CHANNEL_CAPACITY);
+
+        std::thread::spawn(move || {
+            let start = Instant::now();
+
+            let matcher = match request.mode {
+                SearchMode::Regex => RegexMatcherBuilder::new()
+                    .case_insensitive(!request.case_sensitive)
+                    .build(&effective_pattern(&request))
+                    .ok(),
+                SearchMode::Fuzzy => None,
+            };
+
+            // An invalid glob pattern leaves the stream empty, same as an invalid
+            // regex pattern does below - the caller sees no matches rather than an
+            // error it has no channel to receive.
+            let Ok(globs) = GlobFilter::new(&request.include_globs, &request.exclude_globs)
+            else {
+                return;
+            };
+
+            self.blobs.par_iter().for_each_with(tx, |tx, (hash, mmap)| {
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                if deadline.is_some_and(|d| start.elapsed() > d) {
+                    return;
+                }
+
+                // Send one `SearchMatch` per path sharing this blob; returns `false`
+                // once the receiver has hung up so the caller can stop the scan.
+                let mut send_to_all_paths =
+                    |line_number: u64, line: &str, score: i64, before: &[String], after: &[String]| -> bool {
+                        for m in self.fan_out(hash, line_number, line, score, before, after, &globs) {
+                            if tx.send(m).is_err() {
+                                return false;
+                            }
+                        }
+                        true
+                    };
+
+                match (&request.mode, &matcher) {
+                    (SearchMode::Regex, Some(matcher)) => {
+                        let lines: Vec<&str> = std::str::from_utf8(&mmap[..])
+                            .map(|text| text.lines().collect())
+                            .unwrap_or_default();
+                        let mut searcher = Searcher::new();
+                        let _ = searcher.search_slice(
+                            matcher,
+                            &mmap[..],
+                            UTF8(|line_num, line| {
+                                if cancel.load(Ordering::Relaxed)
+                                    || deadline.is_some_and(|d| start.elapsed() > d)
+                                {
+                                    return Ok(false);
+                                }
+
+                                let (before, after) = context_slice(
+                                    &lines,
+                                    line_num,
+                                    request.before_context,
+                                    request.after_context,
+                                );
+                                Ok(send_to_all_paths(line_num, line.trim_end(), 0, &before, &after))
+                            }),
+                        );
+                    }
+                    (SearchMode::Fuzzy, _) => {
+                        let fuzzy = SkimMatcherV2::default();
+                        let needle = if request.case_sensitive {
+                            request.pattern.clone()
+                        } else {
+                            request.pattern.to_lowercase()
+                        };
+
+                        let Ok(text) = std::str::from_utf8(&mmap[..]) else {
+                            return;
+                        };
+                        let lines: Vec<&str> = text.lines().collect();
+
+                        for (idx, line) in lines.iter().enumerate() {
+                            if cancel.load(Ordering::Relaxed)
+                                || deadline.is_some_and(|d| start.elapsed() > d)
+                            {
+                                break;
+                            }
+
+                            let haystack = if request.case_sensitive {
+                                line.to_string()
+                            } else {
+                                line.to_lowercase()
+                            };
+
+                            if let Some(score) = fuzzy.fuzzy_match(&haystack, &needle) {
+                                if score <= FUZZY_SCORE_THRESHOLD {
+                                    continue;
+                                }
+
+                                let line_number = (idx + 1) as u64;
+                                let (before, after) = context_slice(
+                                    &lines,
+                                    line_number,
+                                    request.before_context,
+                                    request.after_context,
+                                );
+                                if !send_to_all_paths(line_number, line, score, &before, &after) {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    (SearchMode::Regex, None) => {
+                        // Invalid regex pattern: nothing to do, caller already
+                        // sees an empty stream rather than an error.
+                    }
+                }
+            });
+        });
+
+        rx
+    }
+}
+
+/// Failure reasons a `Service` call can fail with, distinct enough for a caller (the
+/// socket protocol in `service.rs`, or an embedder) to branch on the kind of failure
+/// instead of string-matching the display message.
+#[derive(Debug)]
+pub enum ServiceError {
+    /// No codebase has been allocated for this PID yet; call `alloc_pid` first.
+    NotAllocated(u32),
+    /// The repo path passed to `alloc_pid` doesn't exist.
+    PathMissing(PathBuf),
+    /// Loading/mmapping the repo into a `MmapCache` failed.
+    LoadFailed(anyhow::Error),
+    /// The search itself failed (e.g. an invalid regex pattern).
+    SearchFailed(anyhow::Error),
+}
+
+impl std::fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceError::NotAllocated(pid) => {
+                write!(f, "PID {} has no allocated codebase. Call alloc_pid first.", pid)
+            }
+            ServiceError::PathMissing(path) => {
+                write!(f, "Repository path does not exist: {}", path.display())
+            }
+            ServiceError::LoadFailed(e) => write!(f, "Failed to load codebase: {}", e),
+            ServiceError::SearchFailed(e) => write!(f, "Search failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ServiceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ServiceError::LoadFailed(e) | ServiceError::SearchFailed(e) => Some(e.as_ref()),
+            ServiceError::NotAllocated(_) | ServiceError::PathMissing(_) => None,
+        }
+    }
+}
+
+/// Core request-handling logic for the search service, independent of any socket or
+/// wire-protocol layer. Embed this directly, or drive it from a `Client` over the
+/// socket transport in `service.rs`, without needing to spin up the latter to test it.
+pub struct Service {
+    codebases: Mutex<HashMap<u32, Arc<MmapCache>>>,
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self {
+            codebases: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Load `repo_dir_path` into memory for `pid`, returning the number of files indexed.
+    pub fn alloc_pid(&self, pid: u32, repo_dir_path: &Path) -> Result<usize, ServiceError> {
+        if !repo_dir_path.exists() {
+            return Err(ServiceError::PathMissing(repo_dir_path.to_path_buf()));
+        }
+
+        let cache = MmapCache::new(repo_dir_path).map_err(ServiceError::LoadFailed)?;
+        let file_count = cache.file_count();
+        self.codebases.lock().unwrap().insert(pid, Arc::new(cache));
+        Ok(file_count)
+    }
+
+    /// Run `request` against `pid`'s allocated codebase
+    pub fn search(&self, pid: u32, request: &SearchRequest) -> Result<SearchResponse, ServiceError> {
+        self.codebase(pid)?
+            .search(request)
+            .map_err(ServiceError::SearchFailed)
+    }
+
+    /// Run `request` against `pid`'s allocated codebase, streaming matches as they're found
+    pub fn search_streaming(
+        &self,
+        pid: u32,
+        request: SearchRequest,
+        cancel: Arc<AtomicBool>,
+        deadline: Option<Duration>,
+    ) -> Result<Receiver<SearchMatch>, ServiceError> {
+        Ok(self.codebase(pid)?.search_streaming(request, cancel, deadline))
+    }
+
+    /// Drop `pid`'s allocated codebase, freeing its mmapped blobs. Called when a client
+    /// disconnects or a worker gives up on it, so long-running services don't leak
+    /// memory across many short-lived client sessions.
+    pub fn free_pid(&self, pid: u32) {
+        self.codebases.lock().unwrap().remove(&pid);
+    }
+
+    fn codebase(&self, pid: u32) -> Result<Arc<MmapCache>, ServiceError> {
+        self.codebases
+            .lock()
+            .unwrap()
+            .get(&pid)
+            .cloned()
+            .ok_or(ServiceError::NotAllocated(pid))
+    }
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -0,0 +1,173 @@
+use crate::ServiceError;
+use serde::{Deserialize, Serialize};
+
+/// Machine-readable failure kind for a `Response`, alongside its human-readable
+/// `error` message, so callers can branch on the kind of failure instead of
+/// string-matching prose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// No codebase has been allocated for this PID yet
+    NotAllocated,
+    /// The repo path passed to `alloc_pid` doesn't exist
+    PathMissing,
+    /// Loading/mmapping the repo into a codebase failed
+    LoadFailed,
+    /// The search itself failed (e.g. an invalid regex pattern)
+    SearchFailed,
+    /// The request itself was malformed or unsupported
+    BadRequest,
+    /// The service failed for reasons unrelated to the request itself (e.g. a worker
+    /// thread crashed mid-search)
+    Internal,
+}
+
+impl From<&ServiceError> for ErrorCode {
+    fn from(e: &ServiceError) -> Self {
+        match e {
+            ServiceError::NotAllocated(_) => ErrorCode::NotAllocated,
+            ServiceError::PathMissing(_) => ErrorCode::PathMissing,
+            ServiceError::LoadFailed(_) => ErrorCode::LoadFailed,
+            ServiceError::SearchFailed(_) => ErrorCode::SearchFailed,
+        }
+    }
+}
+
+/// Request types exchanged between a `Client` and the service over its socket
+///
+/// Every request carries a client-assigned `id`, echoed back on the matching
+/// `Response`, so a single PID can have more than one search in flight on its shared
+/// response socket and match replies up itself (JSON-RPC style correlation).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Request {
+    #[serde(rename = "alloc_pid")]
+    AllocPid {
+        id: u64,
+        pid: u32,
+        repo_dir_path: String,
+    },
+    #[serde(rename = "request_ripgrep")]
+    RequestRipgrep {
+        id: u64,
+        pid: u32,
+        pattern: String,
+        #[serde(default)]
+        case_sensitive: bool,
+        /// Treat `pattern` as a literal string rather than a regex (ripgrep's
+        /// `--fixed-strings`)
+        #[serde(default)]
+        fixed_strings: bool,
+        /// Lines of context to include before/after each match (ripgrep's `-B`/`-A`)
+        #[serde(default)]
+        before_context: usize,
+        #[serde(default)]
+        after_context: usize,
+        /// Only search paths matching at least one of these globs (ripgrep's `-g GLOB`)
+        #[serde(default)]
+        include: Vec<String>,
+        /// Skip paths matching any of these globs (ripgrep's `-g '!GLOB'`)
+        #[serde(default)]
+        exclude: Vec<String>,
+    },
+}
+
+/// Response types sent back over the socket
+///
+/// A search streams as zero or more `chunk` frames followed by one `done` frame
+/// carrying the total match count, rather than one frame holding every match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    pub(crate) id: u64, // echoes the originating Request's id
+    pub(crate) response_status: u8, // 1 = success, 0 = failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+    /// Machine-readable failure kind; always present alongside `error`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) code: Option<ErrorCode>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub(crate) done: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) total_matches: Option<usize>,
+    /// The actual bound port of the per-PID response socket, when that socket is
+    /// `Transport::Tcp` and was bound on an OS-assigned ephemeral port (port 0). Set
+    /// only on the `alloc_pid` success response; `None` for Unix transports, which
+    /// derive a predictable response path from the PID instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) response_port: Option<u16>,
+}
+
+fn is_false(done: &bool) -> bool {
+    !*done
+}
+
+impl Response {
+    pub fn success(id: u64, text: Option<String>) -> Self {
+        Self {
+            id,
+            response_status: 1,
+            text,
+            error: None,
+            code: None,
+            done: false,
+            total_matches: None,
+            response_port: None,
+        }
+    }
+
+    /// Attach the real bound port of an ephemeral-port TCP response socket to this
+    /// response (see `response_port`).
+    pub fn with_response_port(mut self, port: u16) -> Self {
+        self.response_port = Some(port);
+        self
+    }
+
+    pub fn failure(id: u64, code: ErrorCode, error: String) -> Self {
+        Self {
+            id,
+            response_status: 0,
+            text: None,
+            error: Some(error),
+            code: Some(code),
+            done: false,
+            total_matches: None,
+            response_port: None,
+        }
+    }
+
+    /// Build a `failure` response from a `ServiceError`, deriving `code` from its variant
+    /// and `error` from its `Display` message.
+    pub fn from_service_error(id: u64, e: &ServiceError) -> Self {
+        Self::failure(id, ErrorCode::from(e), e.to_string())
+    }
+
+    /// One batch of streamed search matches; more chunks or a final `done` frame follow.
+    pub fn chunk(id: u64, text: String) -> Self {
+        Self {
+            id,
+            response_status: 1,
+            text: Some(text),
+            error: None,
+            code: None,
+            done: false,
+            total_matches: None,
+            response_port: None,
+        }
+    }
+
+    /// The final frame of a streamed search, carrying the total match count.
+    pub fn done(id: u64, total_matches: usize) -> Self {
+        Self {
+            id,
+            response_status: 1,
+            text: None,
+            error: None,
+            code: None,
+            done: true,
+            total_matches: Some(total_matches),
+            response_port: None,
+        }
+    }
+}
@@ -1,79 +1,37 @@
 use anyhow::{Context, Result};
 use crossbeam_channel::{bounded, Receiver, Sender};
-use curserve::MmapCache;
-use serde::{Deserialize, Serialize};
+use curserve::{
+    Conn, ErrorCode, Listener, Request, Response, SearchMode, SearchRequest, SearchTarget, Service,
+    Transport,
+};
 use std::collections::HashMap;
 use std::fs;
-use std::io::{BufRead, BufReader, Write};
-use std::os::unix::net::{UnixListener, UnixStream};
+use std::io::{self, BufRead, BufReader, Write};
 use std::path::Path;
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 use std::thread;
 
-const REQUEST_SOCKET: &str = "/tmp/mem_search_service_requests.sock";
-
-/// Request types from clients
-#[derive(Debug, Deserialize)]
-#[serde(tag = "type")]
-enum Request {
-    #[serde(rename = "alloc_pid")]
-    AllocPid {
-        pid: u32,
-        repo_dir_path: String,
-    },
-    #[serde(rename = "request_ripgrep")]
-    RequestRipgrep {
-        pid: u32,
-        pattern: String,
-        #[serde(default)]
-        case_sensitive: bool,
-    },
-}
-
-/// Response types sent back to clients
-#[derive(Debug, Serialize)]
-struct Response {
-    response_status: u8, // 1 = success, 0 = failure
-    #[serde(skip_serializing_if = "Option::is_none")]
-    text: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    error: Option<String>,
-}
-
-impl Response {
-    fn success(text: Option<String>) -> Self {
-        Self {
-            response_status: 1,
-            text,
-            error: None,
-        }
-    }
-
-    fn failure(error: String) -> Self {
-        Self {
-            response_status: 0,
-            text: None,
-            error: Some(error),
-        }
-    }
-}
-
-/// Shared state between threads
+/// Shared state between threads: the embeddable `Service` plus the socket bookkeeping
+/// needed to route its responses back over per-PID connections.
 struct ServiceState {
-    /// Mapping from PID to memory-mapped codebase
-    codebases: HashMap<u32, MmapCache>,
+    service: Service,
     /// Mapping from PID to response socket stream
-    response_sockets: HashMap<u32, UnixStream>,
+    response_sockets: HashMap<u32, Conn>,
     /// Mapping from PID to response socket listener (for accepting connections)
-    response_listeners: HashMap<u32, UnixListener>,
+    response_listeners: HashMap<u32, Listener>,
+    /// The transport the request socket was bound on; response transports are derived
+    /// from this so they follow the same scheme (Unix path, abstract, or TCP).
+    transport: Transport,
 }
 
 impl ServiceState {
-    fn new() -> Self {
+    fn new(transport: Transport) -> Self {
         Self {
-            codebases: HashMap::new(),
+            service: Service::new(),
             response_sockets: HashMap::new(),
             response_listeners: HashMap::new(),
+            transport,
         }
     }
 }
@@ -81,87 +39,128 @@ impl ServiceState {
 /// Handle alloc_pid request
 fn handle_alloc_pid(
     state: &mut ServiceState,
+    id: u64,
     pid: u32,
     repo_dir_path: String,
 ) -> Result<Response> {
-    let repo_path = Path::new(&repo_dir_path);
-
-    if !repo_path.exists() {
-        return Ok(Response::failure(format!(
-            "Repository path does not exist: {}",
-            repo_dir_path
-        )));
-    }
-
     println!("[PID {}] Allocating codebase: {}", pid, repo_dir_path);
 
-    // Create the MmapCache for this codebase
-    match MmapCache::new(repo_path) {
-        Ok(cache) => {
-            state.codebases.insert(pid, cache);
-
-            // Create response socket listener
-            let response_socket_path = format!("/tmp/qwen_code_response_{}.sock", pid);
-
-            // Remove old socket if it exists
-            let _ = fs::remove_file(&response_socket_path);
-
-            // Create the socket listener (but don't wait for connections here)
-            let listener = UnixListener::bind(&response_socket_path)
-                .context("Failed to bind response socket")?;
+    match state.service.alloc_pid(pid, Path::new(&repo_dir_path)) {
+        Ok(file_count) => {
+            // Create response socket listener, using the same transport scheme as the
+            // request socket (Unix path, Unix abstract, or TCP)
+            let response_transport = state.transport.response_transport(pid);
+            let listener = response_transport
+                .bind()
+                .context("Failed to bind response transport")?;
+            listener
+                .set_nonblocking(true)
+                .context("Failed to set response listener non-blocking")?;
+
+            println!(
+                "[PID {}] Response socket created at {}",
+                pid, response_transport
+            );
+
+            // TCP response transports bind on port 0 (ephemeral); tell the client the
+            // port the OS actually assigned so it can connect to it.
+            let response_port = listener
+                .local_port()
+                .context("Failed to read back response listener port")?;
 
             state.response_listeners.insert(pid, listener);
 
-            println!("[PID {}] Response socket created at {}", pid, response_socket_path);
-
             // Return success immediately - client will connect to response socket after receiving this response
-            Ok(Response::success(Some(format!(
-                "Allocated {} files",
-                state.codebases.get(&pid).unwrap().files.len()
-            ))))
+            let mut response =
+                Response::success(id, Some(format!("Allocated {} files", file_count)));
+            if let Some(port) = response_port {
+                response = response.with_response_port(port);
+            }
+            Ok(response)
         }
-        Err(e) => Ok(Response::failure(format!(
-            "Failed to load codebase: {}",
-            e
-        ))),
+        Err(e) => Ok(Response::from_service_error(id, &e)),
     }
 }
 
-/// Handle request_ripgrep request
-fn handle_ripgrep(
-    state: &ServiceState,
+/// Number of output lines (matches plus their context lines) buffered into one streamed
+/// response frame.
+const STREAM_BATCH_SIZE: usize = 64;
+
+/// Handle request_ripgrep request by streaming matches back as a sequence of chunk
+/// frames, instead of collecting every match into one giant string before replying.
+#[allow(clippy::too_many_arguments)]
+fn handle_ripgrep_streaming(
+    state: &Arc<Mutex<ServiceState>>,
+    id: u64,
     pid: u32,
     pattern: String,
     case_sensitive: bool,
-) -> Result<Response> {
-    // Check if PID has an allocated codebase
-    let cache = match state.codebases.get(&pid) {
-        Some(c) => c,
-        None => {
-            return Ok(Response::failure(format!(
-                "PID {} has no allocated codebase. Call alloc_pid first.",
-                pid
-            )))
+    fixed_strings: bool,
+    before_context: usize,
+    after_context: usize,
+    include: Vec<String>,
+    exclude: Vec<String>,
+) -> Result<()> {
+    println!("[PID {}] Streaming search for pattern: {}", pid, pattern);
+
+    let request = SearchRequest {
+        pattern,
+        mode: SearchMode::Regex,
+        case_sensitive,
+        target: SearchTarget::Contents,
+        fixed_strings,
+        before_context,
+        after_context,
+        include_globs: include,
+        exclude_globs: exclude,
+    };
+
+    let search_result = lock_state(state)
+        .service
+        .search_streaming(pid, request, Arc::new(AtomicBool::new(false)), None);
+
+    let matches_rx = match search_result {
+        Ok(rx) => rx,
+        Err(e) => {
+            let resp = Response::from_service_error(id, &e);
+            return send_response_or_cleanup(&mut lock_state(state), pid, &resp);
         }
     };
 
-    println!("[PID {}] Searching for pattern: {}", pid, pattern);
+    let mut batch = Vec::with_capacity(STREAM_BATCH_SIZE);
+    let mut total = 0usize;
 
-    // Perform the search
-    match cache.search(&pattern, case_sensitive) {
-        Ok(matches) => {
-            // Format output like ripgrep: path:line_num:content
-            let output = matches
-                .iter()
-                .map(|(path, line_num, content)| format!("{}:{}:{}", path, line_num, content))
-                .collect::<Vec<_>>()
-                .join("\n");
+    for m in matches_rx.iter() {
+        // Format output like ripgrep: path:line_num:content for the match itself,
+        // path-content for context lines (ripgrep uses "-" rather than ":" there).
+        for line in &m.before {
+            batch.push(format!("{}-{}", m.path, line));
+        }
+        batch.push(format!("{}:{}:{}", m.path, m.line_number, m.line));
+        for line in &m.after {
+            batch.push(format!("{}-{}", m.path, line));
+        }
+        total += 1;
 
-            println!("[PID {}] Found {} matches", pid, matches.len());
-            Ok(Response::success(Some(output)))
+        if batch.len() >= STREAM_BATCH_SIZE {
+            send_chunk(state, pid, id, &batch)?;
+            batch.clear();
         }
-        Err(e) => Ok(Response::failure(format!("Search failed: {}", e))),
     }
+
+    if !batch.is_empty() {
+        send_chunk(state, pid, id, &batch)?;
+    }
+
+    println!("[PID {}] Found {} matches", pid, total);
+
+    send_response_or_cleanup(&mut lock_state(state), pid, &Response::done(id, total))
+}
+
+/// Send one batch of streamed matches as a `chunk` response frame
+fn send_chunk(state: &Arc<Mutex<ServiceState>>, pid: u32, id: u64, lines: &[String]) -> Result<()> {
+    let resp = Response::chunk(id, lines.join("\n"));
+    send_response_or_cleanup(&mut lock_state(state), pid, &resp)
 }
 
 /// Send response to client's response socket
@@ -180,7 +179,7 @@ fn send_response(state: &mut ServiceState, pid: u32, response: &Response) -> Res
 }
 
 /// Send response directly on a given stream (used for alloc_pid responses)
-fn send_response_on_stream(stream: &mut UnixStream, response: &Response) -> Result<()> {
+fn send_response_on_stream(stream: &mut Conn, response: &Response) -> Result<()> {
     let json = serde_json::to_string(response)?;
     stream.write_all(json.as_bytes())?;
     stream.write_all(b"\n")?; // Newline delimiter
@@ -190,17 +189,13 @@ fn send_response_on_stream(stream: &mut UnixStream, response: &Response) -> Resu
 }
 
 /// Request listener thread - receives requests and adds to queue
-fn request_listener(request_tx: Sender<(Request, UnixStream)>) -> Result<()> {
-    // Remove old socket if it exists
-    let _ = fs::remove_file(REQUEST_SOCKET);
-
-    let listener =
-        UnixListener::bind(REQUEST_SOCKET).context("Failed to bind request socket")?;
+fn request_listener(transport: Transport, request_tx: Sender<(Request, Conn)>) -> Result<()> {
+    let listener = transport.bind().context("Failed to bind request socket")?;
 
-    println!("Request listener started on {}", REQUEST_SOCKET);
+    println!("Request listener started on {}", transport);
 
-    for stream in listener.incoming() {
-        match stream {
+    loop {
+        match listener.accept() {
             Ok(stream) => {
                 // Read request from stream
                 let reader = BufReader::new(stream.try_clone()?);
@@ -231,8 +226,6 @@ fn request_listener(request_tx: Sender<(Request, UnixStream)>) -> Result<()> {
             }
         }
     }
-
-    Ok(())
 }
 
 /// Connection acceptor thread - accepts connections on response sockets
@@ -240,17 +233,17 @@ fn connection_acceptor(state: Arc<Mutex<ServiceState>>) -> Result<()> {
     println!("Connection acceptor thread started");
 
     loop {
-        let mut state = state.lock().unwrap();
+        let mut state = lock_state(&state);
 
         // Check all listeners for pending connections
         let mut connections = Vec::new();
         for (&pid, listener) in &state.response_listeners {
             match listener.accept() {
-                Ok((stream, _)) => {
+                Ok(stream) => {
                     println!("[PID {}] Client connected successfully", pid);
                     connections.push((pid, stream));
                 }
-                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
                     // No pending connection, continue
                 }
                 Err(e) => {
@@ -276,58 +269,163 @@ fn connection_acceptor(state: Arc<Mutex<ServiceState>>) -> Result<()> {
     }
 }
 
-/// Main worker thread - processes requests from queue
-fn request_worker(request_rx: Receiver<(Request, UnixStream)>, state: Arc<Mutex<ServiceState>>) -> Result<()> {
-    println!("Worker thread started");
+/// Number of worker threads processing the request queue, overridable via
+/// `CURSERVE_WORKERS` (default 4 below).
+const DEFAULT_WORKER_COUNT: usize = 4;
+
+fn worker_count() -> usize {
+    std::env::var("CURSERVE_WORKERS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_WORKER_COUNT)
+}
+
+/// Lock `ServiceState`, recovering from a poisoned mutex rather than panicking.
+///
+/// A panic inside one worker while holding this lock would otherwise poison it for
+/// every other worker too, defeating the point of a supervised pool - so a worker
+/// crash is treated as lost in-flight work, not a reason to distrust the state itself.
+fn lock_state(state: &Mutex<ServiceState>) -> std::sync::MutexGuard<'_, ServiceState> {
+    state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// One worker's processing loop - pulls requests off the shared queue until the
+/// channel disconnects. Multiple of these run concurrently, sharing `request_rx`.
+fn request_worker(
+    worker_id: usize,
+    request_rx: Receiver<(Request, Conn)>,
+    state: Arc<Mutex<ServiceState>>,
+    in_flight: Arc<Mutex<Option<(u32, u64)>>>,
+) -> Result<()> {
+    println!("Worker {} started", worker_id);
 
     loop {
         match request_rx.recv() {
             Ok((request, stream)) => {
-                let mut state = state.lock().unwrap();
-
-                let (pid, response, is_alloc_pid) = match &request {
-                    Request::AllocPid { pid, repo_dir_path } => {
-                        (*pid, handle_alloc_pid(&mut state, *pid, repo_dir_path.clone()), true)
+                let (pid, id) = match &request {
+                    Request::AllocPid { pid, id, .. } => (*pid, *id),
+                    Request::RequestRipgrep { pid, id, .. } => (*pid, *id),
+                };
+                *in_flight.lock().unwrap_or_else(|p| p.into_inner()) = Some((pid, id));
+
+                match request {
+                    Request::AllocPid { id, pid, repo_dir_path } => {
+                        let response = handle_alloc_pid(&mut lock_state(&state), id, pid, repo_dir_path);
+                        match response {
+                            Ok(resp) => {
+                                // For alloc_pid, send response directly on the request stream
+                                if let Err(e) = send_response_on_stream(&mut stream.try_clone()?, &resp) {
+                                    eprintln!("[PID {}] Failed to send alloc_pid response: {}", pid, e);
+                                    // Clean up failed allocation
+                                    let mut state = lock_state(&state);
+                                    state.response_sockets.remove(&pid);
+                                    state.response_listeners.remove(&pid);
+                                    state.service.free_pid(pid);
+                                }
+                            }
+                            Err(e) => {
+                                eprintln!("[PID {}] Request handler error: {}", pid, e);
+                            }
+                        }
                     }
                     Request::RequestRipgrep {
+                        id,
                         pid,
                         pattern,
                         case_sensitive,
-                    } => (*pid, handle_ripgrep(&state, *pid, pattern.clone(), *case_sensitive), false),
-                };
-
-                match response {
-                    Ok(resp) => {
-                        if is_alloc_pid {
-                            // For alloc_pid, send response directly on the request stream
-                            if let Err(e) = send_response_on_stream(&mut stream.try_clone()?, &resp) {
-                                eprintln!("[PID {}] Failed to send alloc_pid response: {}", pid, e);
-                                // Clean up failed allocation
-                                state.response_sockets.remove(&pid);
-                                state.response_listeners.remove(&pid);
-                                state.codebases.remove(&pid);
-                            }
-                        } else {
-                            // For other requests, send on response socket
-                            if let Err(e) = send_response(&mut state, pid, &resp) {
-                                eprintln!("[PID {}] Failed to send response: {}", pid, e);
-                                // Clean up dead socket
-                                state.response_sockets.remove(&pid);
-                                state.response_listeners.remove(&pid);
-                                state.codebases.remove(&pid);
-                            }
+                        fixed_strings,
+                        before_context,
+                        after_context,
+                        include,
+                        exclude,
+                    } => {
+                        if let Err(e) = handle_ripgrep_streaming(
+                            &state,
+                            id,
+                            pid,
+                            pattern,
+                            case_sensitive,
+                            fixed_strings,
+                            before_context,
+                            after_context,
+                            include,
+                            exclude,
+                        ) {
+                            eprintln!("[PID {}] Request handler error: {}", pid, e);
                         }
                     }
-                    Err(e) => {
-                        eprintln!("[PID {}] Request handler error: {}", pid, e);
-                    }
                 }
+
+                *in_flight.lock().unwrap_or_else(|p| p.into_inner()) = None;
             }
             Err(e) => {
-                eprintln!("Channel receive error: {}", e);
+                eprintln!("Worker {} channel receive error: {}", worker_id, e);
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Spawn a worker, plus a supervisor that respawns it if it panics.
+///
+/// If a worker dies mid-search, the PID it was serving gets a `Response::failure`
+/// instead of hanging forever waiting for a reply that will never arrive.
+fn spawn_supervised_worker(
+    worker_id: usize,
+    request_rx: Receiver<(Request, Conn)>,
+    state: Arc<Mutex<ServiceState>>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || loop {
+        let rx = request_rx.clone();
+        let worker_state = Arc::clone(&state);
+        let in_flight: Arc<Mutex<Option<(u32, u64)>>> = Arc::new(Mutex::new(None));
+        let tracker = Arc::clone(&in_flight);
+
+        let handle = thread::spawn(move || request_worker(worker_id, rx, worker_state, tracker));
+
+        match handle.join() {
+            Ok(Ok(())) => {
+                // The channel disconnected (service shutting down) - don't respawn.
+                println!("Worker {} exiting (request channel closed)", worker_id);
                 break;
             }
+            Ok(Err(e)) => {
+                eprintln!("Worker {} error, respawning: {}", worker_id, e);
+            }
+            Err(_) => {
+                eprintln!("Worker {} panicked, respawning", worker_id);
+            }
+        }
+
+        if let Some((pid, id)) = in_flight.lock().unwrap_or_else(|p| p.into_inner()).take() {
+            let mut state = lock_state(&state);
+            let resp = Response::failure(
+                id,
+                ErrorCode::Internal,
+                format!("Worker {} crashed while handling this request", worker_id),
+            );
+            if let Err(e) = send_response_or_cleanup(&mut state, pid, &resp) {
+                eprintln!("[PID {}] Failed to notify client after worker crash: {}", pid, e);
+            }
         }
+    })
+}
+
+/// Send a response on a PID's response socket, dropping its state if the send fails
+/// (e.g. the client disconnected). Does nothing if the PID has no response socket.
+fn send_response_or_cleanup(state: &mut ServiceState, pid: u32, response: &Response) -> Result<()> {
+    if !state.response_sockets.contains_key(&pid) {
+        return Ok(());
+    }
+
+    if let Err(e) = send_response(state, pid, response) {
+        state.response_sockets.remove(&pid);
+        state.response_listeners.remove(&pid);
+        state.service.free_pid(pid);
+        return Err(e);
     }
 
     Ok(())
@@ -339,11 +437,13 @@ fn main() -> Result<()> {
     println!("{}", "=".repeat(80));
     println!();
 
+    let transport = Transport::from_env();
+
     // Create shared state
-    let state = Arc::new(Mutex::new(ServiceState::new()));
+    let state = Arc::new(Mutex::new(ServiceState::new(transport.clone())));
 
     // Create channel for communication between listener and worker
-    let (request_tx, request_rx) = bounded::<(Request, UnixStream)>(100);
+    let (request_tx, request_rx) = bounded::<(Request, Conn)>(100);
 
     // Spawn connection acceptor thread
     let acceptor_state = Arc::clone(&state);
@@ -355,30 +455,34 @@ fn main() -> Result<()> {
 
     // Spawn listener thread
     let listener_tx = request_tx.clone();
+    let listener_transport = transport.clone();
     let listener_thread = thread::spawn(move || {
-        if let Err(e) = request_listener(listener_tx) {
+        if let Err(e) = request_listener(listener_transport, listener_tx) {
             eprintln!("Request listener error: {}", e);
         }
     });
 
-    // Spawn worker thread
-    let worker_state = Arc::clone(&state);
-    let worker_thread = thread::spawn(move || {
-        if let Err(e) = request_worker(request_rx, worker_state) {
-            eprintln!("Request worker error: {}", e);
-        }
-    });
+    // Spawn a supervised worker pool, all pulling from the same request queue
+    let pool_size = worker_count();
+    println!("Starting {} worker(s)", pool_size);
+    let worker_threads: Vec<_> = (0..pool_size)
+        .map(|worker_id| spawn_supervised_worker(worker_id, request_rx.clone(), Arc::clone(&state)))
+        .collect();
 
     println!("Service running. Press Ctrl+C to stop.");
     println!();
 
     // Wait for threads
     listener_thread.join().expect("Listener thread panicked");
-    worker_thread.join().expect("Worker thread panicked");
+    for handle in worker_threads {
+        handle.join().expect("Worker supervisor thread panicked");
+    }
     acceptor_thread.join().expect("Acceptor thread panicked");
 
     // Cleanup
-    let _ = fs::remove_file(REQUEST_SOCKET);
+    if let Transport::UnixPath(path) = &transport {
+        let _ = fs::remove_file(path);
+    }
 
     Ok(())
 }
@@ -0,0 +1,152 @@
+//! SimHash fingerprinting and a BK-tree index for near-duplicate line detection.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// Number of bits in a fingerprint
+const BITS: usize = 64;
+
+/// Compute a 64-bit SimHash fingerprint for a line of text.
+///
+/// The line is tokenized on non-alphanumeric boundaries; each token is hashed to 64
+/// bits and contributes +1 to a bit position's running total when that bit is set in
+/// the token's hash, and -1 otherwise. The fingerprint bit is 1 wherever the
+/// accumulated total across all tokens ends up positive.
+pub fn simhash(line: &str) -> u64 {
+    let mut weights = [0i32; BITS];
+
+    for token in tokenize(line) {
+        let mut hasher = DefaultHasher::new();
+        token.hash(&mut hasher);
+        let token_hash = hasher.finish();
+
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if token_hash & (1 << bit) != 0 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    let mut fingerprint = 0u64;
+    for (bit, weight) in weights.iter().enumerate() {
+        if *weight > 0 {
+            fingerprint |= 1 << bit;
+        }
+    }
+    fingerprint
+}
+
+fn tokenize(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|t| !t.is_empty())
+}
+
+/// Hamming distance between two fingerprints
+pub fn hamming(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// A BK-tree indexing 64-bit SimHash fingerprints by Hamming distance (a valid metric,
+/// so the triangle inequality prunes the search), supporting "within tolerance `t`"
+/// nearest-fingerprint queries.
+///
+/// Each node stores the opaque item ids inserted at its exact fingerprint, plus child
+/// nodes keyed by their Hamming distance from this node.
+#[derive(Default)]
+pub struct BkTree {
+    root: Option<Box<BkNode>>,
+}
+
+struct BkNode {
+    fingerprint: u64,
+    items: Vec<usize>,
+    children: HashMap<u32, Box<BkNode>>,
+}
+
+impl BkTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a fingerprint, associating it with an opaque item id (an index into a
+    /// caller-owned side table of the indexed lines).
+    pub fn insert(&mut self, fingerprint: u64, item: usize) {
+        match &mut self.root {
+            None => {
+                self.root = Some(Box::new(BkNode {
+                    fingerprint,
+                    items: vec![item],
+                    children: HashMap::new(),
+                }));
+            }
+            Some(root) => root.insert(fingerprint, item),
+        }
+    }
+
+    /// Remove a previously-inserted `(fingerprint, item)` pair
+    pub fn remove(&mut self, fingerprint: u64, item: usize) {
+        if let Some(root) = &mut self.root {
+            root.remove(fingerprint, item);
+        }
+    }
+
+    /// Return the item ids of every fingerprint within `max_distance` of `query`
+    pub fn query(&self, query: u64, max_distance: u32) -> Vec<usize> {
+        let mut results = Vec::new();
+        if let Some(root) = &self.root {
+            root.query(query, max_distance, &mut results);
+        }
+        results
+    }
+}
+
+impl BkNode {
+    fn insert(&mut self, fingerprint: u64, item: usize) {
+        if fingerprint == self.fingerprint {
+            self.items.push(item);
+            return;
+        }
+
+        let distance = hamming(fingerprint, self.fingerprint);
+        self.children
+            .entry(distance)
+            .or_insert_with(|| {
+                Box::new(BkNode {
+                    fingerprint,
+                    items: Vec::new(),
+                    children: HashMap::new(),
+                })
+            })
+            .insert(fingerprint, item);
+    }
+
+    fn remove(&mut self, fingerprint: u64, item: usize) {
+        if fingerprint == self.fingerprint {
+            self.items.retain(|&i| i != item);
+            return;
+        }
+
+        let distance = hamming(fingerprint, self.fingerprint);
+        if let Some(child) = self.children.get_mut(&distance) {
+            child.remove(fingerprint, item);
+        }
+    }
+
+    fn query(&self, query: u64, max_distance: u32, results: &mut Vec<usize>) {
+        let distance = hamming(query, self.fingerprint);
+        if distance <= max_distance {
+            results.extend_from_slice(&self.items);
+        }
+
+        let lo = distance.saturating_sub(max_distance);
+        let hi = distance + max_distance;
+        for (&edge, child) in &self.children {
+            if edge >= lo && edge <= hi {
+                child.query(query, max_distance, results);
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+/// Where the service listens for requests, and where per-PID response endpoints are
+/// derived from. Resolved once at startup from `CURSERVE_SOCKET`/`CURSERVE_PORT`.
+#[derive(Clone)]
+pub enum Transport {
+    /// A filesystem-path Unix domain socket
+    UnixPath(PathBuf),
+    /// A Linux abstract-namespace Unix domain socket (no filesystem entry)
+    UnixAbstract(String),
+    /// A TCP socket on 127.0.0.1
+    Tcp(u16),
+}
+
+/// Where the service listens for requests by default, when nothing in the
+/// environment overrides it.
+pub const DEFAULT_REQUEST_SOCKET: &str = "/tmp/mem_search_service_requests.sock";
+
+impl Transport {
+    /// Resolve the request-socket transport from the environment, falling back to the
+    /// historical `/tmp` Unix socket path.
+    ///
+    /// `CURSERVE_PORT` takes priority and switches to TCP. Otherwise `CURSERVE_SOCKET`
+    /// overrides the socket path; a value starting with an escaped NUL byte
+    /// (`\x00name`) is bound as a Linux abstract-namespace socket instead of a
+    /// filesystem path.
+    pub fn from_env() -> Self {
+        if let Ok(port) = std::env::var("CURSERVE_PORT") {
+            if let Ok(port) = port.parse() {
+                return Transport::Tcp(port);
+            }
+        }
+
+        let path =
+            std::env::var("CURSERVE_SOCKET").unwrap_or_else(|_| DEFAULT_REQUEST_SOCKET.to_string());
+        match path.strip_prefix("\\x00").or_else(|| path.strip_prefix('\0')) {
+            Some(name) => Transport::UnixAbstract(name.to_string()),
+            None => Transport::UnixPath(PathBuf::from(path)),
+        }
+    }
+
+    /// Derive the response-socket transport for a PID, using the same scheme as this
+    /// (request) transport.
+    pub fn response_transport(&self, pid: u32) -> Self {
+        match self {
+            Transport::UnixPath(path) => {
+                let dir = path.parent().unwrap_or_else(|| Path::new("/tmp"));
+                Transport::UnixPath(dir.join(format!("curserve_response_{}.sock", pid)))
+            }
+            Transport::UnixAbstract(name) => {
+                Transport::UnixAbstract(format!("{}_response_{}", name, pid))
+            }
+            // Port 0 asks the OS for an ephemeral port; the client learns it from the
+            // alloc_pid response's `response_port` rather than a fixed, predictable port.
+            Transport::Tcp(_) => Transport::Tcp(0),
+        }
+    }
+
+    /// Bind a listener for this transport, removing a stale Unix socket file first.
+    pub fn bind(&self) -> Result<Listener> {
+        match self {
+            Transport::UnixPath(path) => {
+                let _ = fs::remove_file(path);
+                Ok(Listener::Unix(
+                    UnixListener::bind(path).context("Failed to bind Unix socket")?,
+                ))
+            }
+            Transport::UnixAbstract(name) => {
+                #[cfg(target_os = "linux")]
+                {
+                    use std::os::linux::net::SocketAddrExt;
+                    let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())
+                        .context("Invalid abstract socket name")?;
+                    Ok(Listener::Unix(
+                        UnixListener::bind_addr(&addr).context("Failed to bind abstract socket")?,
+                    ))
+                }
+                #[cfg(not(target_os = "linux"))]
+                {
+                    anyhow::bail!("Abstract namespace sockets (\\0{}) require Linux", name)
+                }
+            }
+            Transport::Tcp(port) => Ok(Listener::Tcp(
+                TcpListener::bind(("127.0.0.1", *port)).context("Failed to bind TCP socket")?,
+            )),
+        }
+    }
+
+    /// Connect to a listener already bound on this transport, for client use.
+    pub fn connect(&self) -> Result<Conn> {
+        match self {
+            Transport::UnixPath(path) => Ok(Conn::Unix(
+                UnixStream::connect(path).context("Failed to connect to Unix socket")?,
+            )),
+            Transport::UnixAbstract(name) => {
+                #[cfg(target_os = "linux")]
+                {
+                    use std::os::linux::net::SocketAddrExt;
+                    let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())
+                        .context("Invalid abstract socket name")?;
+                    Ok(Conn::Unix(
+                        UnixStream::connect_addr(&addr)
+                            .context("Failed to connect to abstract socket")?,
+                    ))
+                }
+                #[cfg(not(target_os = "linux"))]
+                {
+                    anyhow::bail!("Abstract namespace sockets (\\0{}) require Linux", name)
+                }
+            }
+            Transport::Tcp(port) => Ok(Conn::Tcp(
+                TcpStream::connect(("127.0.0.1", *port)).context("Failed to connect to TCP socket")?,
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Transport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Transport::UnixPath(path) => write!(f, "unix:{}", path.display()),
+            Transport::UnixAbstract(name) => write!(f, "unix-abstract:{}", name),
+            Transport::Tcp(port) => write!(f, "tcp:127.0.0.1:{}", port),
+        }
+    }
+}
+
+/// A bound listener for one of the supported transports
+pub enum Listener {
+    Unix(UnixListener),
+    Tcp(TcpListener),
+}
+
+impl Listener {
+    pub fn accept(&self) -> io::Result<Conn> {
+        match self {
+            Listener::Unix(l) => l.accept().map(|(stream, _)| Conn::Unix(stream)),
+            Listener::Tcp(l) => l.accept().map(|(stream, _)| Conn::Tcp(stream)),
+        }
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        match self {
+            Listener::Unix(l) => l.set_nonblocking(nonblocking),
+            Listener::Tcp(l) => l.set_nonblocking(nonblocking),
+        }
+    }
+
+    /// The port this listener actually bound to, for `Transport::Tcp(0)` (ephemeral
+    /// port) listeners whose caller needs to hand the real port to the other side.
+    /// `None` for Unix transports.
+    pub fn local_port(&self) -> io::Result<Option<u16>> {
+        match self {
+            Listener::Unix(_) => Ok(None),
+            Listener::Tcp(l) => Ok(Some(l.local_addr()?.port())),
+        }
+    }
+}
+
+/// A connected stream for one of the supported transports
+pub enum Conn {
+    Unix(UnixStream),
+    Tcp(TcpStream),
+}
+
+impl Conn {
+    pub fn try_clone(&self) -> io::Result<Conn> {
+        match self {
+            Conn::Unix(s) => Ok(Conn::Unix(s.try_clone()?)),
+            Conn::Tcp(s) => Ok(Conn::Tcp(s.try_clone()?)),
+        }
+    }
+}
+
+impl Read for Conn {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Conn::Unix(s) => s.read(buf),
+            Conn::Tcp(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Conn {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Conn::Unix(s) => s.write(buf),
+            Conn::Tcp(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Conn::Unix(s) => s.flush(),
+            Conn::Tcp(s) => s.flush(),
+        }
+    }
+}
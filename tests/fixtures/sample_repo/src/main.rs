@@ -0,0 +1,8 @@
+fn main() {
+    println!("hello from the fixture repo");
+    println!("{}", greet("world"));
+}
+
+fn greet(name: &str) -> String {
+    format!("hello, {}", name)
+}
@@ -0,0 +1,180 @@
+//! Integration tests for the embeddable `Service`/`Client` pair: start a minimal
+//! socket-backed service on a temp-dir Unix socket, allocate a fixture repo through a
+//! real `Client` connection, and assert on the streamed search results.
+
+use curserve::{
+    Client, Request, Response, RipgrepQuery, SearchMode, SearchRequest, SearchTarget, Service,
+    Transport,
+};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+fn fixture_repo() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample_repo")
+}
+
+/// Wire a `Service` up to a single real client connection on `transport`, just enough
+/// to drive `Client` end-to-end - one request connection, one PID, one response
+/// connection - without pulling in the `service` binary's worker-pool machinery.
+fn spawn_test_service(transport: Transport) {
+    let service = Service::new();
+    let listener = transport.bind().expect("failed to bind test request socket");
+
+    thread::spawn(move || {
+        let conn = listener.accept().expect("failed to accept request connection");
+        let mut reply_conn = conn.try_clone().expect("failed to clone request connection");
+        let reader = BufReader::new(conn);
+        let mut response_conn = None;
+
+        for line in reader.lines() {
+            let Ok(line) = line else { break };
+            let Ok(request) = serde_json::from_str::<Request>(&line) else {
+                continue;
+            };
+
+            match request {
+                Request::AllocPid {
+                    id,
+                    pid,
+                    repo_dir_path,
+                } => {
+                    match service.alloc_pid(pid, Path::new(&repo_dir_path)) {
+                        Ok(file_count) => {
+                            let response_listener = transport
+                                .response_transport(pid)
+                                .bind()
+                                .expect("failed to bind response socket");
+                            // Reply before accepting: `Client::alloc_pid` only connects to
+                            // the response socket once it has read this reply.
+                            send_json_line(
+                                &mut reply_conn,
+                                &Response::success(id, Some(format!("Allocated {} files", file_count))),
+                            );
+                            response_conn =
+                                Some(response_listener.accept().expect("failed to accept response connection"));
+                        }
+                        Err(e) => send_json_line(&mut reply_conn, &Response::from_service_error(id, &e)),
+                    }
+                }
+                Request::RequestRipgrep {
+                    id,
+                    pid,
+                    pattern,
+                    case_sensitive,
+                    fixed_strings,
+                    before_context,
+                    after_context,
+                    include,
+                    exclude,
+                } => {
+                    let Some(response_conn) = response_conn.as_mut() else {
+                        continue;
+                    };
+
+                    let request = SearchRequest {
+                        pattern,
+                        mode: SearchMode::Regex,
+                        case_sensitive,
+                        target: SearchTarget::Contents,
+                        fixed_strings,
+                        before_context,
+                        after_context,
+                        include_globs: include,
+                        exclude_globs: exclude,
+                    };
+
+                    match service.search(pid, &request) {
+                        Ok(result) => {
+                            let lines: Vec<String> = result
+                                .matches
+                                .iter()
+                                .map(|m| format!("{}:{}:{}", m.path, m.line_number, m.line))
+                                .collect();
+                            if !lines.is_empty() {
+                                send_json_line(response_conn, &Response::chunk(id, lines.join("\n")));
+                            }
+                            send_json_line(response_conn, &Response::done(id, lines.len()));
+                        }
+                        Err(e) => send_json_line(response_conn, &Response::from_service_error(id, &e)),
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn send_json_line<T: serde::Serialize>(conn: &mut curserve::Conn, value: &T) {
+    let json = serde_json::to_string(value).expect("failed to serialize response");
+    conn.write_all(json.as_bytes()).expect("failed to write response");
+    conn.write_all(b"\n").expect("failed to write newline");
+    conn.flush().expect("failed to flush response");
+}
+
+#[test]
+fn client_finds_matches_in_allocated_repo() {
+    let dir = tempdir();
+    let transport = Transport::UnixPath(dir.join("requests.sock"));
+    spawn_test_service(transport.clone());
+
+    let mut client = Client::connect(transport).expect("client failed to connect");
+    let allocated = client
+        .alloc_pid(1, fixture_repo().to_str().unwrap())
+        .expect("alloc_pid failed");
+    assert!(allocated.contains("Allocated"));
+
+    let result = client
+        .request_ripgrep(
+            1,
+            RipgrepQuery {
+                pattern: "greet".to_string(),
+                ..Default::default()
+            },
+        )
+        .expect("request_ripgrep failed");
+
+    assert_eq!(result.total_matches, 2);
+    assert!(result.lines.iter().any(|l| l.contains("fn greet")));
+}
+
+#[test]
+fn client_reports_failure_for_unallocated_pid() {
+    let dir = tempdir();
+    let transport = Transport::UnixPath(dir.join("requests.sock"));
+    spawn_test_service(transport.clone());
+
+    let mut client = Client::connect(transport).expect("client failed to connect");
+    client
+        .alloc_pid(2, fixture_repo().to_str().unwrap())
+        .expect("alloc_pid failed");
+
+    // A different, never-allocated PID should surface as an error rather than hang.
+    let err = client
+        .request_ripgrep(
+            99,
+            RipgrepQuery {
+                pattern: "greet".to_string(),
+                ..Default::default()
+            },
+        );
+    // PID 99 has no allocated codebase, so the service answers on the already-open
+    // response connection with a `NotAllocated` failure and this call returns an error.
+    assert!(err.is_err());
+}
+
+fn tempdir() -> PathBuf {
+    let mut dir = std::env::temp_dir();
+    dir.push(format!(
+        "curserve-test-{}-{}",
+        std::process::id(),
+        thread_unique_suffix()
+    ));
+    std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+    dir
+}
+
+fn thread_unique_suffix() -> u64 {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    COUNTER.fetch_add(1, Ordering::Relaxed)
+}